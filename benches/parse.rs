@@ -0,0 +1,79 @@
+//! Compares the hand-written parser behind `FontMetrics::parse` with the
+//! pom parser `afm()` as the crate first had it, the chain of `command()`
+//! and `charcommand()` combinators kept in `original`, on the fonts in
+//! `assets/`.
+//!
+//! Run with `cargo bench`. Over three runs the hand-written parser came
+//! out 13.7-14.2x faster in total: 12.2-14.9x on the kern-heavy Helvetica
+//! and Times, 14.5-19.8x on Courier and 15.3-18.1x on Symbol and
+//! ZapfDingbats.
+
+extern crate afm;
+extern crate pom;
+
+mod original;
+
+use afm::FontMetrics;
+use pom::DataInput;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 50;
+
+fn elapsed<F: FnMut()>(parse: &mut F) -> Duration {
+    let start = Instant::now();
+    parse();
+    start.elapsed()
+}
+
+/// The fastest of several runs of each parser, which is the least disturbed
+/// by other processes. The runs alternate, so that a slow spell of the
+/// machine does not favour either.
+fn time<F: FnMut(), G: FnMut()>(mut pom: F, mut hand_written: G) -> (Duration, Duration) {
+    let mut fastest = (Duration::MAX, Duration::MAX);
+    for _ in 0..ITERATIONS {
+        fastest.0 = fastest.0.min(elapsed(&mut pom));
+        fastest.1 = fastest.1.min(elapsed(&mut hand_written));
+    }
+    fastest
+}
+
+fn main() {
+    let mut files: Vec<_> = fs::read_dir(Path::new("assets"))
+        .unwrap()
+        .map(|file| file.unwrap().path())
+        .filter(|path| path.extension() == Some(OsStr::new("afm")))
+        .collect();
+    files.sort();
+
+    let mut total = (Duration::default(), Duration::default());
+    println!("{:<28} {:>12} {:>12} {:>8}", "file", "afm()", "parse()", "speedup");
+    for path in &files {
+        let data = fs::read(path).unwrap();
+        let (pom, hand_written) = time(
+            || {
+                original::afm().parse(&mut DataInput::new(&data)).unwrap();
+            },
+            || {
+                FontMetrics::parse(&data).unwrap();
+            },
+        );
+        total = (total.0 + pom, total.1 + hand_written);
+        println!(
+            "{:<28} {:>12?} {:>12?} {:>7.1}x",
+            path.file_name().unwrap().to_string_lossy(),
+            pom,
+            hand_written,
+            pom.as_secs_f64() / hand_written.as_secs_f64()
+        );
+    }
+    println!(
+        "{:<28} {:>12?} {:>12?} {:>7.1}x",
+        "total",
+        total.0,
+        total.1,
+        total.0.as_secs_f64() / total.1.as_secs_f64()
+    );
+}
@@ -0,0 +1,39 @@
+//! Writes the AFM files given as arguments as Rust code of
+//! `StaticFontMetrics`, see `FontMetrics::write_static_tables`.
+//!
+//! Run with `cargo run --bin afm-codegen -- FILE... > tables.rs`.
+
+extern crate afm;
+
+use afm::FontMetrics;
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+fn main() {
+    let paths: Vec<_> = env::args_os().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: afm-codegen FILE...");
+        process::exit(2);
+    }
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut generate = || -> Result<(), String> {
+        writeln!(out, "// Generated by afm-codegen, do not edit.")
+            .map_err(|err| err.to_string())?;
+        for path in &paths {
+            let path = path.to_string_lossy();
+            let metrics =
+                FontMetrics::from_path(&*path).map_err(|err| format!("{}: {}", path, err))?;
+            writeln!(out).map_err(|err| err.to_string())?;
+            metrics
+                .write_static_tables(&mut out)
+                .map_err(|err| format!("{}: {}", path, err))?;
+        }
+        Ok(())
+    };
+    if let Err(err) = generate() {
+        eprintln!("afm-codegen: {}", err);
+        process::exit(1);
+    }
+}
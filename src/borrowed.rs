@@ -0,0 +1,1167 @@
+use pom::char_class;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str::{self, FromStr};
+
+use super::{
+    decode_text, digit, entry_error, extra_key, font_metrics, invalid_value, is_extra_key,
+    name_char, string_char, token, unexpected_keyword, AfmError, CharMetric, Command, Composite,
+    CompositePart, DirectionCmd, Font, FontMetrics, GlyphId, Grammar, KernPair, KernPairSet,
+    KernPairs, Ligature, Line, Lines, ParseOptions, Position, Rect, Section, Sink, Span, TrackKern,
+    Warning, CHAR_METRIC_KEYS, COMPOSITE_KEYS, KERN_PAIR_KEYS,
+};
+
+/// Font metrics borrowing the glyph names from the parsed input.
+///
+/// Parsing a `FontMetricsRef` avoids allocating a string for every glyph
+/// name in the char metrics, kern pairs and composites, which dominate
+/// large files. `to_owned` turns it into `FontMetrics`, resolving the names
+/// to `GlyphId`s, which is how `FontMetrics::parse` works.
+///
+/// The lines are read by hand-written scanners accepting the same syntax
+/// as the pom parsers in `command()`, `charcommand()` and friends, and
+/// reporting errors at the same positions.
+#[derive(PartialEq, Debug, Default)]
+pub struct FontMetricsRef<'a> {
+    /// The values outside of the char metrics, kern pairs and composites,
+    /// whose fields are left empty here.
+    pub header: FontMetrics,
+    pub char_metrics: Vec<CharMetricRef<'a>>,
+    pub track_kern: Vec<TrackKern>,
+    pub kern_pairs: Vec<KernPairRef<'a>>,
+    pub kern_pairs0: Vec<KernPairRef<'a>>,
+    pub kern_pairs1: Vec<KernPairRef<'a>>,
+    pub composites: Vec<CompositeRef<'a>>,
+}
+
+/// A borrowed `CharMetric`.
+#[derive(PartialEq, Debug, Default)]
+pub struct CharMetricRef<'a> {
+    pub name: Cow<'a, str>,
+    pub bbox: Rect,
+    pub ligatures: Vec<LigatureRef<'a>>,
+    pub character_code: i32,
+    pub wx: f64,
+    pub w0x: f64,
+    pub w1x: f64,
+    pub wy: f64,
+    pub w0y: f64,
+    pub w1y: f64,
+    pub w: (f64, f64),
+    pub w0: (f64, f64),
+    pub w1: (f64, f64),
+    pub vv: (f64, f64),
+    pub extra_keys: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    pub span: Option<Span>,
+}
+
+/// A borrowed `KernPair`. Names given in hex by `KPH` are decoded.
+#[derive(PartialEq, Debug)]
+pub struct KernPairRef<'a> {
+    pub first_kern_character: Cow<'a, str>,
+    pub second_kern_character: Cow<'a, str>,
+    pub x: f64,
+    pub y: f64,
+    pub span: Option<Span>,
+}
+
+/// A borrowed `Ligature`.
+#[derive(PartialEq, Debug)]
+pub struct LigatureRef<'a> {
+    pub successor: Cow<'a, str>,
+    pub ligature: Cow<'a, str>,
+}
+
+/// A borrowed `Composite`.
+#[derive(PartialEq, Debug)]
+pub struct CompositeRef<'a> {
+    pub name: Cow<'a, str>,
+    pub parts: Vec<CompositePartRef<'a>>,
+    pub span: Option<Span>,
+}
+
+/// A borrowed `CompositePart`.
+#[derive(PartialEq, Debug)]
+pub struct CompositePartRef<'a> {
+    pub name: Cow<'a, str>,
+    pub x_displacement: i32,
+    pub y_displacement: i32,
+}
+
+// Values
+
+fn digits(text: &[u8]) -> usize {
+    text.iter().take_while(|c| c.is_ascii_digit()).count()
+}
+
+/// The length of an unsigned integer without leading zeros.
+fn unsigned_len(text: &[u8]) -> Option<usize> {
+    match text.first() {
+        Some(&b'0') => Some(1),
+        Some(c) if c.is_ascii_digit() => Some(digits(text)),
+        _ => None,
+    }
+}
+
+fn as_str(word: &[u8]) -> &str {
+    // The words checked by the functions below are ASCII.
+    str::from_utf8(word).unwrap()
+}
+
+fn uinteger(word: &[u8]) -> Option<u32> {
+    if unsigned_len(word)? != word.len() {
+        return None;
+    }
+    u32::from_str(as_str(word)).ok()
+}
+
+fn integer(word: &[u8]) -> Option<i32> {
+    let sign = (word.first() == Some(&b'-')) as usize;
+    if sign + unsigned_len(&word[sign..])? != word.len() {
+        return None;
+    }
+    i32::from_str(as_str(word)).ok()
+}
+
+/// A number like `-12.5e3`, with the syntax `number()` accepts.
+fn number(word: &[u8]) -> Option<f64> {
+    let sign = (word.first() == Some(&b'-')) as usize;
+    let mut i = sign + unsigned_len(&word[sign..])?;
+    if i == word.len() && i - sign < 16 {
+        // Most numbers are small integers, which convert exactly without
+        // going through `f64::from_str`.
+        let value = word[sign..]
+            .iter()
+            .fold(0, |value, &c| value * 10 + u64::from(c - b'0')) as f64;
+        return Some(if sign == 1 { -value } else { value });
+    }
+    if word.get(i) == Some(&b'.') {
+        let frac = digits(&word[i + 1..]);
+        if frac == 0 {
+            return None;
+        }
+        i += 1 + frac;
+    }
+    if let Some(&b'e') | Some(&b'E') = word.get(i) {
+        i += 1;
+        if let Some(&b'+') | Some(&b'-') = word.get(i) {
+            i += 1;
+        }
+        let exp = digits(&word[i..]);
+        if exp == 0 {
+            return None;
+        }
+        i += exp;
+    }
+    if i != word.len() {
+        return None;
+    }
+    f64::from_str(as_str(word)).ok()
+}
+
+fn name(word: &[u8]) -> Option<&str> {
+    if word.is_empty() || !word.iter().all(|&c| name_char(c)) {
+        return None;
+    }
+    Some(as_str(word))
+}
+
+/// The hex digits between `<` and `>`.
+fn hex(word: &[u8]) -> Option<&[u8]> {
+    if word.len() < 2 || word[0] != b'<' || word[word.len() - 1] != b'>' {
+        return None;
+    }
+    let digits = &word[1..word.len() - 1];
+    if digits.iter().all(|&c| char_class::hex_digit(c)) {
+        Some(digits)
+    } else {
+        None
+    }
+}
+
+fn hex_integer(word: &[u8]) -> Option<i32> {
+    let digits = hex(word)?;
+    if digits.is_empty() {
+        return None;
+    }
+    i32::from_str_radix(as_str(digits), 16).ok()
+}
+
+/// A name given as hex encoded characters, like `<4165>`.
+fn hex_name(word: &[u8]) -> Option<String> {
+    let digits = hex(word)?;
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(as_str(pair), 16).ok().map(char::from))
+        .collect()
+}
+
+/// Splits `text` into words separated by spaces and, if `semicolons` is
+/// set, `;`. Returns `None` if there are more words than `words` holds.
+fn words<'a>(text: &'a [u8], semicolons: bool, words: &mut [&'a [u8]]) -> Option<usize> {
+    let separator = |c: &u8| char_class::space(*c) || (semicolons && *c == b';');
+    let mut count = 0;
+    for word in text.split(separator).filter(|word| !word.is_empty()) {
+        *words.get_mut(count)? = word;
+        count += 1;
+    }
+    Some(count)
+}
+
+// Header
+
+/// Reads the value of a header line with the syntax of the pom parsers,
+/// stopping at the first column it cannot accept.
+struct Scanner<'a> {
+    text: &'a [u8],
+    column: usize,
+    /// Whether the value ended early, which pom reports at the start of the
+    /// line rather than at `column`.
+    incomplete: bool,
+}
+
+type Scan<C> = fn(&mut Scanner<'_>) -> Option<C>;
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.column).cloned()
+    }
+
+    fn skip(&mut self, class: fn(u8) -> bool) -> usize {
+        let len = self.text[self.column..]
+            .iter()
+            .take_while(|&&c| class(c))
+            .count();
+        self.column += len;
+        len
+    }
+
+    fn space(&mut self) -> Option<()> {
+        if self.skip(char_class::space) > 0 {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Checks that only spaces are left.
+    fn end(&self) -> Option<()> {
+        if self.text[self.column..]
+            .iter()
+            .all(|&c| char_class::space(c))
+        {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Reads a space and the value following it up to the end of the line.
+    fn value<C>(&mut self, scan: Scan<C>) -> Option<C> {
+        self.space()?;
+        let value = scan(self)?;
+        self.end()?;
+        Some(value)
+    }
+
+    fn unsigned(&mut self) -> Option<()> {
+        self.incomplete = self.peek().is_none();
+        let len = unsigned_len(&self.text[self.column..])?;
+        self.column += len;
+        Some(())
+    }
+
+    /// Converts the text from `start` on, failing at `start` like pom does.
+    fn convert<O: FromStr>(&mut self, start: usize) -> Option<O> {
+        let value = O::from_str(as_str(&self.text[start..self.column])).ok();
+        if value.is_none() {
+            self.column = start;
+        }
+        value
+    }
+
+    fn uinteger(&mut self) -> Option<u32> {
+        let start = self.column;
+        self.unsigned()?;
+        self.convert(start)
+    }
+
+    fn integer(&mut self) -> Option<i32> {
+        let start = self.column;
+        if self.peek() == Some(b'-') {
+            self.column += 1;
+        }
+        self.unsigned()?;
+        self.convert(start)
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        let start = self.column;
+        if self.peek() == Some(b'-') {
+            self.column += 1;
+        }
+        self.unsigned()?;
+        let frac = self.column;
+        if self.peek() == Some(b'.') {
+            self.column += 1;
+            if self.skip(digit) == 0 {
+                self.column = frac;
+            }
+        }
+        let exp = self.column;
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.column += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.column += 1;
+            }
+            if self.skip(digit) == 0 {
+                self.column = exp;
+            }
+        }
+        self.convert(start)
+    }
+
+    fn numbers(&mut self) -> Option<(f64, f64)> {
+        let first = self.number()?;
+        self.space()?;
+        Some((first, self.number()?))
+    }
+
+    fn bbox(&mut self) -> Option<Rect> {
+        let start = self.column;
+        let mut corners = [0.0; 3];
+        for corner in &mut corners {
+            match self
+                .number()
+                .and_then(|number| self.space().map(|_| number))
+            {
+                Some(number) => *corner = number,
+                None => {
+                    // The corners are a repetition for pom, failing as a whole.
+                    self.column = start;
+                    self.incomplete = false;
+                    return None;
+                }
+            }
+        }
+        let [llx, lly, urx] = corners;
+        Some(Rect::new(llx, lly, urx, self.number()?))
+    }
+
+    fn boolean(&mut self) -> Option<bool> {
+        let rest = &self.text[self.column..];
+        for &(word, value) in &[(&b"true"[..], true), (&b"false"[..], false)] {
+            if rest.starts_with(word) {
+                self.column += word.len();
+                return Some(value);
+            }
+        }
+        // pom reports where the input stops matching `false`, tried last.
+        let matching = rest
+            .iter()
+            .zip(b"false")
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.column += matching;
+        self.incomplete = matching == rest.len();
+        None
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let start = self.column;
+        if self.skip(string_char) == 0 {
+            return None;
+        }
+        Some(decode_text(&self.text[start..self.column]).into_owned())
+    }
+
+    /// Where the value failed to parse.
+    fn error_position(&self, line: &Line) -> Position {
+        if self.incomplete {
+            line.position(line.token(0).0)
+        } else {
+            line.position(self.column)
+        }
+    }
+
+    /// The text of a `Comment`, which may be left out.
+    fn comment(&mut self) -> Option<String> {
+        let start = self.column;
+        let comment = self.space().and_then(|_| self.string());
+        if comment.is_none() {
+            self.column = start;
+        }
+        self.end()?;
+        Some(comment.unwrap_or_default())
+    }
+}
+
+/// The scanner for the value of a header keyword known to `command()`.
+fn header_scan(keyword: &[u8]) -> Option<Scan<Command>> {
+    let scan: Scan<Command> = match keyword {
+        b"Version" => |s| s.string().map(Command::Version),
+        b"MetricsSets" | b"MetricsSet" => |s| s.integer().map(Command::MetricsSet),
+        b"FontName" => |s| s.string().map(Command::FontName),
+        b"FullName" => |s| s.string().map(Command::FullName),
+        b"FamilyName" => |s| s.string().map(Command::FamilyName),
+        b"Weight" => |s| s.string().map(Command::Weight),
+        b"FontBBox" => |s| s.bbox().map(Command::FontBBox),
+        b"Notice" => |s| s.string().map(Command::Notice),
+        b"EncodingScheme" => |s| s.string().map(Command::EncodingScheme),
+        b"MappingScheme" => |s| s.uinteger().map(Command::MappingScheme),
+        b"EscChar" => |s| s.uinteger().map(Command::EscChar),
+        b"CharacterSet" => |s| s.string().map(Command::CharacterSet),
+        b"Characters" => |s| s.uinteger().map(Command::Characters),
+        b"IsBaseFont" => |s| s.boolean().map(Command::IsBaseFont),
+        b"VVector" => |s| s.numbers().map(|(x, y)| Command::VVector(x, y)),
+        b"IsFixedV" => |s| s.boolean().map(Command::IsFixedV),
+        b"CapHeight" => |s| s.number().map(Command::CapHeight),
+        b"XHeight" => |s| s.number().map(Command::XHeight),
+        b"Ascender" => |s| s.number().map(Command::Ascender),
+        b"Descender" => |s| s.number().map(Command::Descender),
+        b"StdHW" => |s| s.number().map(Command::StdHW),
+        b"StdVW" => |s| s.number().map(Command::StdVW),
+        _ => return None,
+    };
+    Some(scan)
+}
+
+/// The scanner for the value of a keyword known to `direction_command()`.
+fn direction_scan(keyword: &[u8]) -> Option<Scan<DirectionCmd>> {
+    let scan: Scan<DirectionCmd> = match keyword {
+        b"UnderlinePosition" => |s| s.number().map(DirectionCmd::UnderlinePosition),
+        b"UnderlineThickness" => |s| s.number().map(DirectionCmd::UnderlineThickness),
+        b"ItalicAngle" => |s| s.number().map(DirectionCmd::ItalicAngle),
+        b"CharWidth" => |s| s.numbers().map(|(x, y)| DirectionCmd::CharWidth(x, y)),
+        b"IsFixedPitch" => |s| s.boolean().map(DirectionCmd::IsFixedPitch),
+        _ => return None,
+    };
+    Some(scan)
+}
+
+fn value_scanner<'l>(line: &'l Line) -> (&'l [u8], Scanner<'l>) {
+    let (column, keyword) = line.token(0);
+    let scanner = Scanner {
+        text: &line.text,
+        column: column + keyword.len(),
+        incomplete: false,
+    };
+    (keyword, scanner)
+}
+
+/// Parses a header line like `header_command()`.
+fn header_command(lines: &Lines, line: &Line, section: Section) -> Result<Command, AfmError> {
+    let (keyword, mut value) = value_scanner(line);
+    let command = match (keyword, header_scan(keyword), direction_scan(keyword)) {
+        (b"Comment", _, _) => value.comment().map(Command::Comment),
+        (_, Some(scan), _) => value.value(scan),
+        // Direction metrics outside of a `StartDirection` block apply to direction 0.
+        (_, None, Some(scan)) => value
+            .value(scan)
+            .map(|cmd| Command::Direction(0, vec![cmd])),
+        _ if is_extra_key(lines, keyword, &[]) => {
+            let (key, value) = extra_key(line, line.token(0).0, line.text.len());
+            return Ok(Command::Extra(key, value));
+        }
+        _ => {
+            let position = line.position(line.token(0).0);
+            return Err(unexpected_keyword(keyword, section, position));
+        }
+    };
+    command.ok_or_else(|| invalid_value(keyword, section, value.error_position(line)))
+}
+
+/// Parses a line of a `StartDirection` block like `direction_entry()`.
+fn direction_entry(line: &Line) -> Result<DirectionCmd, AfmError> {
+    let (keyword, mut value) = value_scanner(line);
+    let scan = direction_scan(keyword).ok_or_else(|| {
+        let position = line.position(line.token(0).0);
+        unexpected_keyword(keyword, Section::Direction, position)
+    })?;
+    value
+        .value(scan)
+        .ok_or_else(|| invalid_value(keyword, Section::Direction, value.error_position(line)))
+}
+
+// Entries
+
+/// Applies the key of a `C ... ;` segment to `metric`, leaving it unchanged
+/// unless the segment is well formed.
+fn char_key<'a>(metric: &mut CharMetricRef<'a>, segment: &[&'a [u8]]) -> Option<()> {
+    match *segment {
+        [b"C", code] => metric.character_code = integer(code)?,
+        [b"CH", code] => metric.character_code = hex_integer(code)?,
+        [b"WX", wx] => metric.wx = number(wx)?,
+        [b"W0X", w0x] => metric.w0x = number(w0x)?,
+        [b"W1X", w1x] => metric.w1x = number(w1x)?,
+        [b"WY", wy] => metric.wy = number(wy)?,
+        [b"W0Y", w0y] => metric.w0y = number(w0y)?,
+        [b"W1Y", w1y] => metric.w1y = number(w1y)?,
+        [b"W", x, y] => metric.w = (number(x)?, number(y)?),
+        [b"W0", x, y] => metric.w0 = (number(x)?, number(y)?),
+        [b"W1", x, y] => metric.w1 = (number(x)?, number(y)?),
+        [b"VV", x, y] => metric.vv = (number(x)?, number(y)?),
+        [b"N", name] => metric.name = Cow::Borrowed(self::name(name)?),
+        [b"B", llx, lly, urx, ury] => {
+            metric.bbox = Rect::new(number(llx)?, number(lly)?, number(urx)?, number(ury)?)
+        }
+        [b"L", successor, ligature] => metric.ligatures.push(LigatureRef {
+            successor: Cow::Borrowed(name(successor)?),
+            ligature: Cow::Borrowed(name(ligature)?),
+        }),
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Parses the `C ... ;` line whose text is `text` key by key, like
+/// `char_metric()`.
+fn char_metric<'t>(
+    lines: &mut Lines,
+    line: &Line,
+    text: &'t [u8],
+) -> Result<CharMetricRef<'t>, AfmError> {
+    let mut metric = CharMetricRef::default();
+    let mut start = 0;
+    for segment in text.split(|&c| c == b';') {
+        let end = start + segment.len();
+        let mut buf = [&segment[..0]; 5];
+        let parsed = words(segment, false, &mut buf).and_then(|count| match count {
+            0 => Some(()),
+            _ => char_key(&mut metric, &buf[..count]),
+        });
+        if parsed.is_none() {
+            let (column, keyword) = token(text, start);
+            if is_extra_key(lines, keyword, CHAR_METRIC_KEYS) {
+                let value = &text[column + keyword.len()..end];
+                let value = decode_text(value);
+                let value = match value {
+                    Cow::Borrowed(value) => Cow::Borrowed(value.trim()),
+                    Cow::Owned(value) => Cow::Owned(value.trim().to_string()),
+                };
+                metric
+                    .extra_keys
+                    .push((Cow::Borrowed(as_str(keyword)), value));
+            } else {
+                let position = line.position(column);
+                lines.recover(if CHAR_METRIC_KEYS.contains(&keyword) {
+                    invalid_value(keyword, Section::CharMetrics, position)
+                } else {
+                    unexpected_keyword(keyword, Section::CharMetrics, position)
+                })?;
+            }
+        }
+        start = end + 1;
+    }
+    Ok(metric)
+}
+
+fn kern_pair<'t>(line: &Line, text: &'t [u8]) -> Result<KernPairRef<'t>, AfmError> {
+    let mut buf = [&text[..0]; 5];
+    let pair = words(text, false, &mut buf).and_then(|count| {
+        let (first, second, x, y) = match buf[..count] {
+            [b"KP", first, second, x, y] => (first, second, number(x)?, number(y)?),
+            [b"KPH", first, second, x, y] => {
+                return Some(KernPairRef {
+                    first_kern_character: Cow::Owned(hex_name(first)?),
+                    second_kern_character: Cow::Owned(hex_name(second)?),
+                    x: number(x)?,
+                    y: number(y)?,
+                    span: None,
+                });
+            }
+            [b"KPX", first, second, x] => (first, second, number(x)?, 0.0),
+            [b"KPY", first, second, y] => (first, second, 0.0, number(y)?),
+            _ => return None,
+        };
+        Some(KernPairRef {
+            first_kern_character: Cow::Borrowed(name(first)?),
+            second_kern_character: Cow::Borrowed(name(second)?),
+            x,
+            y,
+            span: None,
+        })
+    });
+    pair.ok_or_else(|| entry_error(line, Section::KernPairs, KERN_PAIR_KEYS))
+}
+
+/// Parses `CC name count ; PCC name x y ; ...`.
+fn composite<'t>(line: &Line, text: &'t [u8]) -> Result<CompositeRef<'t>, AfmError> {
+    let mut words = text
+        .split(|&c| char_class::space(c) || c == b';')
+        .filter(|word| !word.is_empty());
+    let mut parse = || {
+        if words.next()? != b"CC" {
+            return None;
+        }
+        let name = Cow::Borrowed(name(words.next()?)?);
+        let count = uinteger(words.next()?)?;
+        let mut parts = Vec::new();
+        for _ in 0..count {
+            if words.next()? != b"PCC" {
+                return None;
+            }
+            parts.push(CompositePartRef {
+                name: Cow::Borrowed(self::name(words.next()?)?),
+                x_displacement: integer(words.next()?)?,
+                y_displacement: integer(words.next()?)?,
+            });
+        }
+        if words.next().is_some() {
+            return None;
+        }
+        Some(CompositeRef {
+            name,
+            parts,
+            span: None,
+        })
+    };
+    parse().ok_or_else(|| entry_error(line, Section::Composites, COMPOSITE_KEYS))
+}
+
+/// The hand-written parsers `FontMetrics::parse` uses. Lines read from a
+/// stream are owned, so the names taken from them are copied.
+pub(crate) struct HandWritten;
+
+impl<'a> Grammar<'a> for HandWritten {
+    fn header_command(
+        &self,
+        lines: &Lines<'a>,
+        line: &Line<'a>,
+        section: Section,
+    ) -> Result<Command, AfmError> {
+        header_command(lines, line, section)
+    }
+
+    fn direction_entry(&self, line: &Line<'a>) -> Result<DirectionCmd, AfmError> {
+        direction_entry(line)
+    }
+
+    fn char_metric(
+        &self,
+        lines: &mut Lines<'a>,
+        line: &Line<'a>,
+    ) -> Result<CharMetricRef<'a>, AfmError> {
+        match line.text {
+            Cow::Borrowed(text) => char_metric(lines, line, text),
+            Cow::Owned(ref text) => char_metric(lines, line, text).map(CharMetricRef::into_owned),
+        }
+    }
+
+    fn kern_pair(&self, line: &Line<'a>) -> Result<KernPairRef<'a>, AfmError> {
+        match line.text {
+            Cow::Borrowed(text) => kern_pair(line, text),
+            Cow::Owned(ref text) => kern_pair(line, text).map(KernPairRef::into_owned),
+        }
+    }
+
+    fn composite(&self, line: &Line<'a>) -> Result<CompositeRef<'a>, AfmError> {
+        match line.text {
+            Cow::Borrowed(text) => composite(line, text),
+            Cow::Owned(ref text) => composite(line, text).map(CompositeRef::into_owned),
+        }
+    }
+}
+
+impl<'a> Sink<'a> for FontMetricsRef<'a> {
+    fn header_key(&mut self, _: &[u8], _: &[u8]) {}
+
+    fn char_metric(&mut self, metric: CharMetricRef<'a>, _: &[GlyphId]) {
+        self.char_metrics.push(metric);
+    }
+
+    fn kern_pair(&mut self, set: KernPairSet, pair: KernPairRef<'a>, _: &[GlyphId]) {
+        match set {
+            KernPairSet::Pairs => self.kern_pairs.push(pair),
+            KernPairSet::Pairs0 => self.kern_pairs0.push(pair),
+            KernPairSet::Pairs1 => self.kern_pairs1.push(pair),
+        }
+    }
+
+    fn track_kern(&mut self, track: TrackKern) {
+        self.track_kern.push(track);
+    }
+
+    fn composite(&mut self, composite: CompositeRef<'a>, _: &[GlyphId]) {
+        self.composites.push(composite);
+    }
+}
+
+impl<'a> Font<'a, FontMetricsRef<'a>> {
+    /// The metrics read.
+    pub(crate) fn into_metrics(self) -> FontMetricsRef<'a> {
+        let mut metrics = FontMetricsRef {
+            header: self.header,
+            ..self.sink
+        };
+        metrics.header.fill_directions();
+        metrics
+    }
+}
+
+impl<'a> FontMetricsRef<'a> {
+    /// Parses the content of an AFM file, borrowing glyph names from it.
+    pub fn parse(input: &'a [u8]) -> Result<FontMetricsRef<'a>, AfmError> {
+        FontMetricsRef::parse_with(input, &ParseOptions::default()).map(|(metrics, _)| metrics)
+    }
+
+    /// Parses the content of an AFM file, also returning the problems
+    /// skipped in lenient mode.
+    ///
+    /// Kern pairs, ligatures and composites naming glyphs without char
+    /// metrics are kept, and the names reported as `UnknownGlyph` warnings
+    /// in strict mode too.
+    pub fn parse_with(
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<(FontMetricsRef<'a>, Vec<Warning>), AfmError> {
+        let lines = &mut Lines::new(input, options);
+        let metrics = font_metrics(lines, &HandWritten, FontMetricsRef::default())?;
+        let metrics = metrics.into_metrics();
+        Ok((metrics, lines.warnings.split_off(0)))
+    }
+
+    /// Copies the metrics into `FontMetrics`, equal to what
+    /// `FontMetrics::parse` returns for the same input. Names of glyphs
+    /// without char metrics go to `unknown_glyphs`.
+    ///
+    /// This is not a cheap clone: it builds a map of the glyph names and
+    /// indexes the copied char metrics. To copy single entries, see
+    /// `KernPairRef::resolve` and the like.
+    pub fn to_owned<'s>(&'s self) -> FontMetrics {
+        // Every name resolves, to an unknown glyph if need be, so no entry
+        // is left out.
+        let mut resolver = Resolver::new(&self.char_metrics);
+        let mut glyph = |name| Some(resolver.glyph(name));
+        let char_metrics = self.char_metrics.iter().map(|metric| CharMetric {
+            ligatures: metric.ligatures(&mut glyph),
+            ..metric.copy()
+        });
+        let mut metrics = FontMetrics {
+            char_metrics: char_metrics.collect(),
+            track_kern: self.track_kern.clone(),
+            ..self.header.clone()
+        };
+        metrics.fill_version_defaults();
+
+        let mut resolve = |pairs: &'s [KernPairRef<'a>]| {
+            let pairs = pairs
+                .iter()
+                .filter_map(|pair| pair.resolve_with(&mut glyph));
+            pairs.collect::<KernPairs>()
+        };
+        metrics.kern_pairs = resolve(&self.kern_pairs);
+        metrics.kern_pairs0 = resolve(&self.kern_pairs0);
+        metrics.kern_pairs1 = resolve(&self.kern_pairs1);
+        let composites = self.composites.iter();
+        let composites = composites.filter_map(|composite| composite.resolve_with(&mut glyph));
+        metrics.composites = composites.collect();
+        metrics.unknown_glyphs = resolver.unknown_glyphs;
+        metrics
+    }
+}
+
+/// Resolves the glyph names of a `FontMetricsRef` to ids with one lookup
+/// each, giving the names without char metrics ids of unknown glyphs.
+struct Resolver<'s> {
+    ids: HashMap<&'s str, GlyphId>,
+    unknown_glyphs: Vec<String>,
+}
+
+impl<'s> Resolver<'s> {
+    fn new(char_metrics: &'s [CharMetricRef]) -> Resolver<'s> {
+        let mut ids = HashMap::with_capacity(char_metrics.len());
+        for (i, metric) in char_metrics.iter().enumerate() {
+            ids.entry(&metric.name[..]).or_insert(GlyphId(i as u32));
+        }
+        Resolver {
+            ids,
+            unknown_glyphs: Vec::new(),
+        }
+    }
+
+    fn glyph(&mut self, name: &'s str) -> GlyphId {
+        let unknown_glyphs = &mut self.unknown_glyphs;
+        *self.ids.entry(name).or_insert_with(|| {
+            unknown_glyphs.push(name.to_string());
+            GlyphId::unknown(unknown_glyphs.len() - 1)
+        })
+    }
+}
+
+/// Turns a borrowed name into an owned one.
+fn owned(name: Cow<str>) -> Cow<'static, str> {
+    Cow::Owned(name.into_owned())
+}
+
+impl<'a> CharMetricRef<'a> {
+    /// Copies the borrowed names, detaching the metric from the input.
+    pub fn into_owned(self) -> CharMetricRef<'static> {
+        CharMetricRef {
+            name: owned(self.name),
+            bbox: self.bbox,
+            ligatures: self
+                .ligatures
+                .into_iter()
+                .map(LigatureRef::into_owned)
+                .collect(),
+            character_code: self.character_code,
+            wx: self.wx,
+            w0x: self.w0x,
+            w1x: self.w1x,
+            wy: self.wy,
+            w0y: self.w0y,
+            w1y: self.w1y,
+            w: self.w,
+            w0: self.w0,
+            w1: self.w1,
+            vv: self.vv,
+            extra_keys: self
+                .extra_keys
+                .into_iter()
+                .map(|(key, value)| (owned(key), owned(value)))
+                .collect(),
+            span: self.span,
+        }
+    }
+
+    /// Copies the metric without its ligatures.
+    pub(crate) fn copy(&self) -> CharMetric {
+        CharMetric {
+            name: self.name.to_string(),
+            bbox: self.bbox,
+            ligatures: Vec::new(),
+            character_code: self.character_code,
+            wx: self.wx,
+            w0x: self.w0x,
+            w1x: self.w1x,
+            wy: self.wy,
+            w0y: self.w0y,
+            w1y: self.w1y,
+            w: self.w,
+            w0: self.w0,
+            w1: self.w1,
+            vv: self.vv,
+            extra_keys: self
+                .extra_keys
+                .iter()
+                .map(|(key, value)| (String::from(&**key), String::from(&**value)))
+                .collect(),
+            span: self.span,
+        }
+    }
+
+    fn ligatures<'s, F>(&'s self, mut glyph: F) -> Vec<Ligature>
+    where
+        F: FnMut(&'s str) -> Option<GlyphId>,
+    {
+        let ligatures = self.ligatures.iter();
+        ligatures
+            .filter_map(|ligature| ligature.resolve_with(&mut glyph))
+            .collect()
+    }
+
+    /// Copies the metric, resolving its ligatures against the glyphs of
+    /// `metrics`, including `unknown_glyphs`, and leaving out those naming
+    /// other glyphs.
+    pub fn resolve(&self, metrics: &FontMetrics) -> CharMetric {
+        CharMetric {
+            ligatures: self.ligatures(|name| metrics.resolve_glyph(name)),
+            ..self.copy()
+        }
+    }
+}
+
+impl<'a> KernPairRef<'a> {
+    /// Copies the borrowed names, detaching the pair from the input.
+    pub fn into_owned(self) -> KernPairRef<'static> {
+        KernPairRef {
+            first_kern_character: owned(self.first_kern_character),
+            second_kern_character: owned(self.second_kern_character),
+            x: self.x,
+            y: self.y,
+            span: self.span,
+        }
+    }
+
+    /// Resolves the pair against the glyphs of `metrics`, including
+    /// `unknown_glyphs`, or `None` if it names another glyph.
+    pub fn resolve(&self, metrics: &FontMetrics) -> Option<KernPair> {
+        self.resolve_with(|name| metrics.resolve_glyph(name))
+    }
+
+    fn resolve_with<'s, F>(&'s self, mut glyph: F) -> Option<KernPair>
+    where
+        F: FnMut(&'s str) -> Option<GlyphId>,
+    {
+        Some(KernPair {
+            first_kern_character: glyph(&self.first_kern_character)?,
+            second_kern_character: glyph(&self.second_kern_character)?,
+            x: self.x,
+            y: self.y,
+            span: self.span,
+        })
+    }
+}
+
+impl<'a> LigatureRef<'a> {
+    /// Copies the borrowed names, detaching the ligature from the input.
+    pub fn into_owned(self) -> LigatureRef<'static> {
+        LigatureRef {
+            successor: owned(self.successor),
+            ligature: owned(self.ligature),
+        }
+    }
+
+    /// Resolves the ligature against the glyphs of `metrics`, including
+    /// `unknown_glyphs`, or `None` if it names another glyph.
+    pub fn resolve(&self, metrics: &FontMetrics) -> Option<Ligature> {
+        self.resolve_with(|name| metrics.resolve_glyph(name))
+    }
+
+    fn resolve_with<'s, F>(&'s self, mut glyph: F) -> Option<Ligature>
+    where
+        F: FnMut(&'s str) -> Option<GlyphId>,
+    {
+        Some(Ligature {
+            successor: glyph(&self.successor)?,
+            ligature: glyph(&self.ligature)?,
+        })
+    }
+}
+
+impl<'a> CompositeRef<'a> {
+    /// Copies the borrowed names, detaching the composite from the input.
+    pub fn into_owned(self) -> CompositeRef<'static> {
+        let parts = self.parts.into_iter().map(|part| CompositePartRef {
+            name: owned(part.name),
+            x_displacement: part.x_displacement,
+            y_displacement: part.y_displacement,
+        });
+        CompositeRef {
+            name: owned(self.name),
+            parts: parts.collect(),
+            span: self.span,
+        }
+    }
+
+    /// Resolves the parts against the glyphs of `metrics`, including
+    /// `unknown_glyphs`, or `None` if one names another glyph.
+    pub fn resolve(&self, metrics: &FontMetrics) -> Option<Composite> {
+        self.resolve_with(|name| metrics.resolve_glyph(name))
+    }
+
+    fn resolve_with<'s, F>(&'s self, mut glyph: F) -> Option<Composite>
+    where
+        F: FnMut(&'s str) -> Option<GlyphId>,
+    {
+        let parts = self.parts.iter().map(|part| {
+            Some(CompositePart {
+                glyph: glyph(&part.name)?,
+                x_displacement: part.x_displacement,
+                y_displacement: part.y_displacement,
+            })
+        });
+        Some(Composite {
+            name: self.name.to_string(),
+            parts: parts.collect::<Option<_>>()?,
+            span: self.span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pom::{self, DataInput};
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::path::Path;
+    use {afm, GlyphId};
+
+    static AFM: &[u8] = b"StartFontMetrics 2.0
+FontName Test
+StartCharMetrics 7
+C 65 ; WX 667 ; N A ; B 14 0 654 718 ;
+CH <42> ; W 722 10 ; N H ; B 19 0 703 662 ; Q 1 2 ;
+C -1 ; WX 5e2 ; N p ; B 5 -217.5 470 460 ;
+C 86 ; WX 667 ; N V ; B 6 0 661 718 ;
+C 102 ; WX 278 ; N f ; B 14 0 314 728 ; L f ff ;
+C -1 ; WX 556 ; N ff ; B 14 0 592 728 ;
+C 194 ; WX 333 ; N acute ; B 93 593 317 734 ;
+EndCharMetrics
+StartKernData
+StartKernPairs 2
+KPH <41> <56> -80 5
+KPY A H 20
+EndKernPairs
+EndKernData
+StartComposites 1
+CC Aacute 2 ; PCC A 0 0 ; PCC acute 195 212 ;
+EndComposites
+EndFontMetrics
+";
+
+    #[test]
+    fn borrow_glyph_names() {
+        let options = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        let (metrics, _) = FontMetricsRef::parse_with(AFM, &options).unwrap();
+        assert_eq!(metrics.header.font_name, "Test");
+        assert_eq!(metrics.char_metrics[1].character_code, 0x42);
+        assert_eq!(metrics.char_metrics[1].extra_keys[0].0, "Q");
+        assert_eq!(metrics.kern_pairs[0].first_kern_character, "A");
+        assert_eq!(metrics.kern_pairs[1].y, 20.0);
+        assert_eq!(metrics.composites[0].parts[1].name, "acute");
+        match metrics.kern_pairs[1].second_kern_character {
+            Cow::Borrowed(name) => assert_eq!(name, "H"),
+            Cow::Owned(_) => panic!("the name should be borrowed"),
+        }
+
+        let (owned, _) = FontMetrics::parse_with(AFM, &options).unwrap();
+        assert_eq!(metrics.to_owned(), owned);
+        assert_eq!(owned.descender, -217.5);
+        assert_eq!(owned.kern_pairs[0].second_kern_character, GlyphId(3));
+        assert_eq!(owned.composites[0].parts[1].glyph, GlyphId(6));
+
+        // Single entries resolve against the owned metrics.
+        assert_eq!(
+            metrics.kern_pairs[1].resolve(&owned),
+            Some(owned.kern_pairs[1].clone())
+        );
+        assert_eq!(
+            metrics.char_metrics[4].resolve(&owned),
+            owned.char_metrics[4]
+        );
+        let composite = metrics.composites[0].resolve(&owned);
+        assert_eq!(composite, Some(owned.composites[0].clone()));
+        let pair = KernPairRef {
+            first_kern_character: Cow::Borrowed("A"),
+            second_kern_character: Cow::Borrowed("B"),
+            x: -10.0,
+            y: 0.0,
+            span: None,
+        };
+        assert_eq!(pair.resolve(&owned), None);
+    }
+
+    #[test]
+    fn resolve_names_before_their_char_metrics() {
+        let input = b"StartFontMetrics 4.1
+StartComposites 1
+CC Aacute 3 ; PCC A 0 0 ; PCC acute 195 212 ; PCC ring 0 0 ;
+EndComposites
+StartKernData
+StartKernPairs1 2
+KPX B A -10
+KPX caron B 20
+EndKernPairs
+StartKernPairs 2
+KPX A ring -30
+KPX dotless A 5
+EndKernPairs
+EndKernData
+StartCharMetrics 4
+C 65 ; WX 600 ; N A ; L B AB ; L f fi ;
+C 66 ; WX 600 ; N B ;
+C -1 ; WX 600 ; N A ;
+C 180 ; WX 333 ; N acute ;
+EndCharMetrics
+EndFontMetrics
+";
+        let options = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        let (metrics, warnings) = FontMetricsRef::parse_with(input, &options).unwrap();
+        let (owned, owned_warnings) = FontMetrics::parse_with(input, &options).unwrap();
+        assert_eq!(metrics.to_owned(), owned);
+        assert_eq!(warnings, owned_warnings);
+        assert_eq!(
+            owned.unknown_glyphs,
+            vec!["AB", "f", "fi", "ring", "dotless", "caron"]
+        );
+        assert_eq!(owned.kern_pairs1[0].second_kern_character, GlyphId(0));
+        assert_eq!(owned.composites[0].parts[2].glyph, GlyphId::unknown(3));
+    }
+
+    /// The result of the pom parsers, which the hand-written parser must
+    /// agree with.
+    fn parse_with_pom(input: &[u8]) -> Result<FontMetrics, String> {
+        afm()
+            .parse(&mut DataInput::new(input))
+            .map_err(|err| match err {
+                pom::Error::Custom { message, .. } => message,
+                err => panic!("unexpected error {:?}", err),
+            })
+    }
+
+    #[test]
+    fn report_errors_like_pom() {
+        let inputs: &[&[u8]] = &[
+            b"StartFontMetrics 4.1\nStartCharMetrics 1\nC 32 ; WX x ;\nEndCharMetrics\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nStartCharMetrics 1\nC 32 ; Q 1 ;\nEndCharMetrics\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nStartKernData\nStartKernPairs 1\nKPX A\nEndKernPairs\nEndKernData\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nStartComposites 1\nCC A 2 ; PCC B 0 0 ;\nEndComposites\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nCapHeight 12x\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nCapHeight -\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nCapHeight\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nFontBBox 1 2 x 4\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nFontBBox 1 2 3 4 5\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nCharacters 99999999999\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nFontName a\tb\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nComment \x01\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nStartDirection 0\nCharWidth 1\nEndDirection\nEndFontMetrics\n",
+            b"StartFontMetrics 4.1\nStartDirection 0\nVVector 1 2\nEndDirection\nEndFontMetrics\n",
+        ];
+        for input in inputs {
+            assert_eq!(
+                FontMetrics::parse(input).map_err(|err| err.to_string()),
+                parse_with_pom(input)
+            );
+        }
+    }
+
+    #[test]
+    fn parse_headers_like_pom() {
+        let input = b"StartFontMetrics 4.1
+Comment
+Comment  Two spaces  
+FontName Test Font
+FontBBox -1.5 -2e2 1E+3 0.25
+MetricsSets 2
+IsFixedV true
+VVector 500 900
+Characters 0
+ItalicAngle -12
+StartDirection 1
+CharWidth 0 -1000
+IsFixedPitch false
+EndDirection
+EndFontMetrics
+";
+        let metrics = FontMetrics::parse(input).unwrap();
+        assert_eq!(metrics.comments, vec!["", "Two spaces  "]);
+        assert_eq!(metrics.font_bbox, Rect::new(-1.5, -200.0, 1000.0, 0.25));
+        assert_eq!(Ok(metrics), parse_with_pom(input));
+    }
+
+    #[test]
+    fn parse_assets_like_pom() {
+        for file in fs::read_dir(Path::new("assets")).unwrap() {
+            let path = file.unwrap().path();
+            if path.extension() != Some(OsStr::new("afm")) {
+                continue;
+            }
+            let data = fs::read(&path).unwrap();
+            let metrics = FontMetricsRef::parse(&data).unwrap().to_owned();
+            assert_eq!(FontMetrics::parse(&data).unwrap(), metrics);
+            assert_eq!(Ok(metrics), parse_with_pom(&data), "{}", path.display());
+        }
+    }
+}
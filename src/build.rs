@@ -0,0 +1,117 @@
+use super::{
+    CharMetric, CharMetricRef, Composite, CompositePart, CompositeRef, Font, FontMetrics, GlyphId,
+    KernPair, KernPairRef, KernPairSet, Ligature, Sink, TrackKern,
+};
+
+/// Builds `FontMetrics` while a file is read, taking the glyph ids of
+/// entries from the names `Font` interns, so that no name is looked up
+/// twice.
+#[derive(Default)]
+pub(crate) struct Builder {
+    char_metrics: Vec<CharMetric>,
+    track_kern: Vec<TrackKern>,
+    kern_pairs: Vec<KernPair>,
+    kern_pairs0: Vec<KernPair>,
+    kern_pairs1: Vec<KernPair>,
+    composites: Vec<Composite>,
+}
+
+impl<'a> Sink<'a> for Builder {
+    fn header_key(&mut self, _: &[u8], _: &[u8]) {}
+
+    fn char_metric(&mut self, metric: CharMetricRef<'a>, glyphs: &[GlyphId]) {
+        let ligatures = glyphs.chunks(2).map(|ids| Ligature {
+            successor: ids[0],
+            ligature: ids[1],
+        });
+        self.char_metrics.push(CharMetric {
+            ligatures: ligatures.collect(),
+            ..metric.copy()
+        });
+    }
+
+    fn kern_pair(&mut self, set: KernPairSet, pair: KernPairRef<'a>, glyphs: &[GlyphId]) {
+        let pairs = match set {
+            KernPairSet::Pairs => &mut self.kern_pairs,
+            KernPairSet::Pairs0 => &mut self.kern_pairs0,
+            KernPairSet::Pairs1 => &mut self.kern_pairs1,
+        };
+        pairs.push(KernPair {
+            first_kern_character: glyphs[0],
+            second_kern_character: glyphs[1],
+            x: pair.x,
+            y: pair.y,
+            span: pair.span,
+        });
+    }
+
+    fn track_kern(&mut self, track: TrackKern) {
+        self.track_kern.push(track);
+    }
+
+    fn composite(&mut self, composite: CompositeRef<'a>, glyphs: &[GlyphId]) {
+        let parts = composite.parts.iter().zip(glyphs);
+        let parts = parts.map(|(part, &glyph)| CompositePart {
+            glyph,
+            x_displacement: part.x_displacement,
+            y_displacement: part.y_displacement,
+        });
+        self.composites.push(Composite {
+            name: composite.name.into_owned(),
+            parts: parts.collect(),
+            span: composite.span,
+        });
+    }
+}
+
+impl<'a> Font<'a, Builder> {
+    /// The metrics read, equal to what `FontMetricsRef::to_owned` gives.
+    pub(crate) fn into_metrics(self) -> FontMetrics {
+        let mut sink = self.sink;
+        let forward = &self.forward;
+        let mut unknown_glyphs = Vec::new();
+        if !forward.is_empty() {
+            // The names used before their char metrics were read resolve to
+            // them now, the others to unknown glyphs, numbered in the order
+            // `to_owned` meets them.
+            let mut resolved: Vec<_> = forward.iter().map(|&(_, id)| id).collect();
+            let mut resolve = |id: &mut GlyphId| {
+                if let Some(i) = id.unknown_index() {
+                    *id = *resolved[i].get_or_insert_with(|| {
+                        unknown_glyphs.push(forward[i].0.clone());
+                        GlyphId::unknown(unknown_glyphs.len() - 1)
+                    });
+                }
+            };
+            for metric in &mut sink.char_metrics {
+                for ligature in &mut metric.ligatures {
+                    resolve(&mut ligature.successor);
+                    resolve(&mut ligature.ligature);
+                }
+            }
+            let pairs = sink.kern_pairs.iter_mut().chain(&mut sink.kern_pairs0);
+            for pair in pairs.chain(&mut sink.kern_pairs1) {
+                resolve(&mut pair.first_kern_character);
+                resolve(&mut pair.second_kern_character);
+            }
+            for composite in &mut sink.composites {
+                for part in &mut composite.parts {
+                    resolve(&mut part.glyph);
+                }
+            }
+        }
+        let mut metrics = FontMetrics {
+            char_metrics: sink.char_metrics.into(),
+            track_kern: sink.track_kern,
+            kern_pairs: sink.kern_pairs.into(),
+            kern_pairs0: sink.kern_pairs0.into(),
+            kern_pairs1: sink.kern_pairs1.into(),
+            composites: sink.composites,
+            unknown_glyphs,
+            ..self.header
+        };
+        metrics.fill_directions();
+        metrics.fill_version_defaults();
+        metrics
+    }
+}
@@ -0,0 +1,359 @@
+use std::io::{self, Write};
+
+use super::{DirectionMetrics, FontMetrics, GlyphId, Rect};
+
+/// The name of the static holding the metrics of the font `font_name`, in
+/// upper snake case, such as `TIMES_BOLD_ITALIC` for `Times-BoldItalic`.
+pub fn static_name(font_name: &str) -> String {
+    let mut name = String::new();
+    let mut lower = false;
+    for c in font_name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if lower && c.is_ascii_uppercase() {
+                name.push('_');
+            }
+            name.push(c.to_ascii_uppercase());
+            lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        } else if lower {
+            name.push('_');
+            lower = false;
+        }
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert_str(0, "FONT_");
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+/// `value` as a Rust expression. Debug formatting writes `NaN` and `inf`,
+/// which are not Rust, so those become the constants of `f64`.
+fn number(value: f64) -> String {
+    if value.is_nan() {
+        "f64::NAN".to_string()
+    } else if value == f64::INFINITY {
+        "f64::INFINITY".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "f64::NEG_INFINITY".to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn rect(rect: &Rect) -> String {
+    format!(
+        "Rect::new({}, {}, {}, {})",
+        number(rect.llx),
+        number(rect.lly),
+        number(rect.urx),
+        number(rect.ury)
+    )
+}
+
+/// Writes `ids` as the elements of a slice, several to a line.
+fn write_ids(out: &mut impl Write, field: &str, ids: &[usize]) -> io::Result<()> {
+    writeln!(out, "    {}: &[", field)?;
+    for line in ids.chunks(8) {
+        let ids: Vec<_> = line.iter().map(|id| format!("GlyphId({}),", id)).collect();
+        writeln!(out, "        {}", ids.join(" "))?;
+    }
+    writeln!(out, "    ],")
+}
+
+impl FontMetrics {
+    /// Writes the metrics as the Rust code of a `StaticFontMetrics`, a
+    /// `pub static` named by `static_name` after `font_name`, such as
+    /// `TIMES_ROMAN`. The code expects `GlyphId`, `Ligature`, `Rect`,
+    /// `StaticCharMetric`, `StaticFontMetrics` and `StaticKernPair` to be
+    /// in scope.
+    ///
+    /// Fails with `InvalidInput` if a kern pair or ligature refers to a
+    /// glyph without char metrics, including those of `unknown_glyphs`,
+    /// which the tables have no room for.
+    ///
+    /// The tables have no room either for `kern_pairs0`, `kern_pairs1`,
+    /// `track_kern` and `composites`. They are left out, and a comment at
+    /// the top of the code counts those the font has. A build script can
+    /// include fonts this way:
+    ///
+    /// ```no_run
+    /// # use afm::FontMetrics;
+    /// # use std::{env, fs::File, path::Path};
+    /// // build.rs
+    /// let metrics = FontMetrics::from_path("fonts/Custom.afm").unwrap();
+    /// let path = Path::new(&env::var("OUT_DIR").unwrap()).join("custom.rs");
+    /// metrics.write_static_tables(&mut File::create(path).unwrap()).unwrap();
+    ///
+    /// // lib.rs
+    /// // use afm::{GlyphId, Ligature, Rect, StaticCharMetric, StaticFontMetrics, StaticKernPair};
+    /// // include!(concat!(env!("OUT_DIR"), "/custom.rs"));
+    /// ```
+    pub fn write_static_tables(&self, out: &mut impl Write) -> io::Result<()> {
+        let known = |id: GlyphId| {
+            if self.glyph(id).is_some() {
+                Ok(id)
+            } else {
+                let message = format!("glyph {} has no char metrics", id.0);
+                Err(io::Error::new(io::ErrorKind::InvalidInput, message))
+            }
+        };
+        let direction = self.direction0.clone().unwrap_or_default();
+        let DirectionMetrics {
+            underline_position,
+            underline_thickness,
+            italic_angle,
+            is_fixed_pitch,
+            ..
+        } = direction;
+
+        let left_out = [
+            ("kern_pairs0", self.kern_pairs0.len()),
+            ("kern_pairs1", self.kern_pairs1.len()),
+            ("track_kern", self.track_kern.len()),
+            ("composites", self.composites.len()),
+        ];
+        let left_out: Vec<_> = left_out
+            .iter()
+            .filter(|&&(_, count)| count > 0)
+            .map(|&(field, count)| format!("{} {}", count, field))
+            .collect();
+        if !left_out.is_empty() {
+            writeln!(out, "// Left out: {}.", left_out.join(", "))?;
+        }
+        writeln!(out, "/// The metrics of `{}`.", self.font_name)?;
+        writeln!(
+            out,
+            "pub static {}: StaticFontMetrics = StaticFontMetrics {{",
+            static_name(&self.font_name)
+        )?;
+        let strings = [
+            ("font_name", &self.font_name),
+            ("full_name", &self.full_name),
+            ("family_name", &self.family_name),
+            ("weight", &self.weight),
+            ("encoding_scheme", &self.encoding_scheme),
+            ("character_set", &self.character_set),
+        ];
+        for (field, value) in &strings {
+            writeln!(out, "    {}: {:?},", field, value)?;
+        }
+        writeln!(out, "    font_bbox: {},", rect(&self.font_bbox))?;
+        let numbers = [
+            ("cap_height", self.cap_height),
+            ("x_height", self.x_height),
+            ("ascender", self.ascender),
+            ("descender", self.descender),
+            ("underline_position", underline_position),
+            ("underline_thickness", underline_thickness),
+            ("italic_angle", italic_angle),
+        ];
+        for &(field, value) in &numbers {
+            writeln!(out, "    {}: {},", field, number(value))?;
+        }
+        writeln!(out, "    is_fixed_pitch: {},", is_fixed_pitch)?;
+
+        writeln!(out, "    char_metrics: &[")?;
+        for metric in &self.char_metrics {
+            let mut ligatures = Vec::new();
+            for ligature in &metric.ligatures {
+                ligatures.push(format!(
+                    "Ligature {{ successor: GlyphId({}), ligature: GlyphId({}) }}",
+                    known(ligature.successor)?.0,
+                    known(ligature.ligature)?.0
+                ));
+            }
+            writeln!(
+                out,
+                "        StaticCharMetric::new({:?}, {}, {}, {}, &[{}]),",
+                metric.name,
+                metric.character_code,
+                number(metric.wx),
+                rect(&metric.bbox),
+                ligatures.join(", ")
+            )?;
+        }
+        writeln!(out, "    ],")?;
+
+        let mut names: Vec<_> = (0..self.char_metrics.len()).collect();
+        names.sort_by_key(|&i| &self.char_metrics[i].name);
+        write_ids(out, "names", &names)?;
+        let mut encoded: Vec<_> = (0..self.char_metrics.len())
+            .filter(|&i| self.char_metrics[i].character_code >= 0)
+            .collect();
+        encoded.sort_by_key(|&i| self.char_metrics[i].character_code);
+        write_ids(out, "encoded", &encoded)?;
+
+        let mut pairs = Vec::new();
+        for pair in &self.kern_pairs {
+            let first = known(pair.first_kern_character)?;
+            let second = known(pair.second_kern_character)?;
+            pairs.push((first, second, pair.x, pair.y));
+        }
+        pairs.sort_by_key(|&(first, second, _, _)| (first, second));
+        writeln!(out, "    kern_pairs: &[")?;
+        for (first, second, x, y) in pairs {
+            writeln!(
+                out,
+                "        StaticKernPair::new({}, {}, {}, {}),",
+                first.0,
+                second.0,
+                number(x),
+                number(y)
+            )?;
+        }
+        writeln!(out, "    ],")?;
+        writeln!(out, "}};")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {KernPair, Metrics, StaticCharMetric, StaticFontMetrics, StaticKernPair};
+
+    #[test]
+    fn name_statics() {
+        assert_eq!(static_name("Times-Roman"), "TIMES_ROMAN");
+        assert_eq!(
+            static_name("Helvetica-BoldOblique"),
+            "HELVETICA_BOLD_OBLIQUE"
+        );
+        assert_eq!(static_name("ZapfDingbats"), "ZAPF_DINGBATS");
+        assert_eq!(
+            static_name("NewCenturySchlbk-Roman"),
+            "NEW_CENTURY_SCHLBK_ROMAN"
+        );
+        assert_eq!(static_name("3of9 Barcode"), "FONT_3OF9_BARCODE");
+        assert_eq!(static_name(""), "FONT");
+    }
+
+    #[test]
+    fn write_invalid_tables() {
+        let mut metrics = FontMetrics::parse(
+            b"StartFontMetrics 4.1\nStartCharMetrics 1\nC 65 ; WX 600 ; N A ;\nEndCharMetrics\nEndFontMetrics\n",
+        )
+        .unwrap();
+        metrics.kern_pairs.push(KernPair {
+            first_kern_character: GlyphId(0),
+            second_kern_character: GlyphId(1),
+            x: -50.0,
+            y: 0.0,
+            span: None,
+        });
+        let err = metrics.write_static_tables(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "glyph 1 has no char metrics");
+    }
+
+    #[test]
+    fn write_non_finite_numbers() {
+        let mut metrics = FontMetrics::parse(
+            b"StartFontMetrics 4.1\nStartCharMetrics 1\nC 65 ; WX 600 ; N A ;\nEndCharMetrics\nEndFontMetrics\n",
+        )
+        .unwrap();
+        metrics.cap_height = f64::NAN;
+        metrics.font_bbox.urx = f64::INFINITY;
+        metrics.char_metrics.edit()[0].wx = f64::NEG_INFINITY;
+        let mut out = Vec::new();
+        metrics.write_static_tables(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("    cap_height: f64::NAN,\n"));
+        assert!(out.contains("    font_bbox: Rect::new(0.0, 0.0, f64::INFINITY, 0.0),\n"));
+        assert!(out.contains("StaticCharMetric::new(\"A\", 65, f64::NEG_INFINITY, "));
+    }
+
+    #[test]
+    fn count_left_out_sections() {
+        let input = b"StartFontMetrics 4.1
+StartCharMetrics 2
+C 65 ; N A ;
+C 194 ; N acute ;
+EndCharMetrics
+StartKernData
+StartTrackKern 1
+TrackKern -1 6 0 72 -1
+EndTrackKern
+StartKernPairs1 1
+KPY A A 10
+EndKernPairs
+EndKernData
+StartComposites 1
+CC A 2 ; PCC A 0 0 ; PCC acute 100 200 ;
+EndComposites
+EndFontMetrics
+";
+        let metrics = FontMetrics::parse(input).unwrap();
+        let mut out = Vec::new();
+        metrics.write_static_tables(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("// Left out: 1 kern_pairs1, 1 track_kern, 1 composites.\n"));
+        assert!(out.contains("    kern_pairs: &[\n    ],\n"));
+    }
+
+    /// What `write_static_tables` writes for the input of `write_tables`.
+    static TEST: StaticFontMetrics = StaticFontMetrics {
+        font_name: "Test",
+        full_name: "",
+        family_name: "",
+        weight: "",
+        encoding_scheme: "",
+        character_set: "",
+        font_bbox: Rect::new(0.0, 0.0, 0.0, 0.0),
+        cap_height: 0.0,
+        x_height: 0.0,
+        ascender: 0.0,
+        descender: 0.0,
+        underline_position: 0.0,
+        underline_thickness: 0.0,
+        italic_angle: 0.0,
+        is_fixed_pitch: false,
+        char_metrics: &[
+            StaticCharMetric::new("V", 86, 0.0, Rect::new(0.0, 0.0, 0.0, 0.0), &[]),
+            StaticCharMetric::new("A", 65, 0.0, Rect::new(0.0, 0.0, 0.0, 0.0), &[]),
+        ],
+        names: &[GlyphId(1), GlyphId(0)],
+        encoded: &[GlyphId(1), GlyphId(0)],
+        kern_pairs: &[
+            StaticKernPair::new(0, 1, 0.0, 20.5),
+            StaticKernPair::new(1, 0, -80.0, 0.0),
+        ],
+    };
+
+    #[test]
+    fn write_tables() {
+        let input = b"StartFontMetrics 4.1
+FontName Test
+StartCharMetrics 2
+C 86 ; N V ;
+C 65 ; N A ;
+EndCharMetrics
+StartKernData
+StartKernPairs 2
+KPX A V -80
+KPY V A 20.5
+EndKernPairs
+EndKernData
+EndFontMetrics
+";
+        let metrics = FontMetrics::parse(input).unwrap();
+        let mut out = Vec::new();
+        metrics.write_static_tables(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("/// The metrics of `Test`.\npub static TEST: "));
+        assert!(out.contains(
+            "        StaticCharMetric::new(\"V\", 86, 0.0, Rect::new(0.0, 0.0, 0.0, 0.0), &[]),\n"
+        ));
+        assert!(out.contains("    names: &[\n        GlyphId(1), GlyphId(0),\n    ],\n"));
+        assert!(out.contains("        StaticKernPair::new(0, 1, 0.0, 20.5),\n        StaticKernPair::new(1, 0, -80.0, 0.0),\n"));
+
+        let (a, v) = (GlyphId(1), GlyphId(0));
+        assert_eq!(TEST.glyph_id("A"), Some(a));
+        assert_eq!(TEST.glyph_id("B"), None);
+        assert_eq!(TEST.glyph_by_code(86).unwrap().name, "V");
+        assert_eq!(TEST.glyph_id_by_code(66), None);
+        assert_eq!(TEST.kerning(a, v), metrics.kerning(a, v));
+        assert_eq!(TEST.kerning(v, a), Some((0.0, 20.5)));
+        assert_eq!(TEST.kerning(a, a), None);
+        let names: Vec<_> = TEST.encoded_glyphs().map(|metric| metric.name).collect();
+        assert_eq!(names, vec!["A", "V"]);
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::FontMetrics;
+
+/// The date of a `Comment Creation Date: Thu May  1 12:49:17 1997`, in
+/// the format of C's `ctime`, found in Adobe's AFM files. Displays as in
+/// ISO 8601, such as `1997-05-01T12:49:17`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub struct CreationDate {
+    pub year: u32,
+    /// From 1 for January to 12.
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+static MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+static WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// A number of at most `max`.
+fn bounded(word: &str, max: u32) -> Option<u32> {
+    if word.is_empty() || !word.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    u32::from_str(word).ok().filter(|&n| n <= max)
+}
+
+/// The number of days of the `month`, from 1 for January, in `year`.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl CreationDate {
+    /// Parses a date like `Thu May  1 12:49:17 1997`, where the weekday
+    /// may be left out.
+    fn parse(text: &str) -> Option<CreationDate> {
+        let mut words = text.split_whitespace().peekable();
+        if words.peek().is_some_and(|word| WEEKDAYS.contains(word)) {
+            words.next();
+        }
+        let month = words.next()?;
+        let month = MONTHS.iter().position(|&name| name == month)? as u32 + 1;
+        let day = bounded(words.next()?, 31).filter(|&day| day > 0)?;
+        let mut time = words.next()?.split(':');
+        let hour = bounded(time.next()?, 23)?;
+        let minute = bounded(time.next()?, 59)?;
+        // 60 for a leap second.
+        let second = bounded(time.next()?, 60)?;
+        let year = bounded(words.next()?, 9999)?;
+        if time.next().is_some() || words.next().is_some() {
+            return None;
+        }
+        if day > days_in_month(year, month) {
+            return None;
+        }
+        Some(CreationDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+}
+
+impl fmt::Display for CreationDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// The printer memory a font takes up, from `Comment VMusage 43909 54934`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct VmUsage {
+    /// The bytes of memory the font needs at least.
+    pub min: u32,
+    /// The bytes of memory the font needs at most.
+    pub max: u32,
+}
+
+impl FontMetrics {
+    /// The text of the first comment starting with the word `key`, after
+    /// it.
+    fn comment_value(&self, key: &str) -> Option<&str> {
+        self.comments.iter().find_map(|comment| {
+            let value = comment.strip_prefix(key)?;
+            match value.chars().next() {
+                Some(c) if !c.is_whitespace() => None,
+                _ => Some(value.trim()),
+            }
+        })
+    }
+
+    /// The number of `Comment UniqueID 43068`, the unique id of the font
+    /// Adobe assigned.
+    pub fn unique_id(&self) -> Option<u32> {
+        u32::from_str(self.comment_value("UniqueID")?).ok()
+    }
+
+    /// The date of `Comment Creation Date: Thu May  1 12:49:17 1997`.
+    pub fn creation_date(&self) -> Option<CreationDate> {
+        CreationDate::parse(self.comment_value("Creation Date:")?)
+    }
+
+    /// The numbers of `Comment VMusage 43909 54934`.
+    pub fn vm_usage(&self) -> Option<VmUsage> {
+        let mut words = self.comment_value("VMusage")?.split_whitespace();
+        let min = u32::from_str(words.next()?).ok()?;
+        let max = u32::from_str(words.next()?).ok()?;
+        if words.next().is_some() {
+            return None;
+        }
+        Some(VmUsage { min, max })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn read_comments() {
+        let data = fs::read("assets/Times-Roman.afm").unwrap();
+        let metrics = FontMetrics::parse(&data).unwrap();
+        assert_eq!(metrics.unique_id(), Some(43068));
+        let date = metrics.creation_date().unwrap();
+        assert_eq!(date.to_string(), "1997-05-01T12:49:17");
+        assert_eq!(
+            metrics.vm_usage(),
+            Some(VmUsage {
+                min: 43909,
+                max: 54934,
+            })
+        );
+
+        let metrics = FontMetrics {
+            comments: vec![
+                "UniqueIDs 1".to_string(),
+                "UniqueID 5000001".to_string(),
+                "Creation Date: Feb 29 23:59:60 2016".to_string(),
+                "VMusage 1 2 3".to_string(),
+            ],
+            ..FontMetrics::default()
+        };
+        assert_eq!(metrics.unique_id(), Some(5_000_001));
+        assert_eq!(
+            metrics.creation_date().map(|date| date.to_string()),
+            Some("2016-02-29T23:59:60".to_string())
+        );
+        assert_eq!(metrics.vm_usage(), None);
+        let date = CreationDate::parse("Tue Feb 29 00:00:00 2000").unwrap();
+        assert_eq!(date.to_string(), "2000-02-29T00:00:00");
+        assert_eq!(CreationDate::parse("Dec 31 00:00:00 1999").unwrap().day, 31);
+
+        for date in &[
+            "Thu May 1 12:49 1997",
+            "Thu Mai 1 12:49:17 1997",
+            "Thu May 0 12:49:17 1997",
+            "Thu May 32 12:49:17 1997",
+            "Thu Apr 31 12:49:17 1997",
+            "Thu Feb 30 12:49:17 2016",
+            "Thu Feb 29 12:49:17 1997",
+            "Thu Feb 29 12:49:17 1900",
+            "Thu May 1 24:00:00 1997",
+            "Thu May 1 12:49:17 1997 EST",
+            "1997-05-01",
+        ] {
+            assert_eq!(CreationDate::parse(date), None, "{}", date);
+        }
+    }
+}
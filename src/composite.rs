@@ -0,0 +1,240 @@
+use pom::Parser;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{
+    block, check_count, end_of_input, font_command, hex_integer, keyword_value, space, start_line,
+    uinteger, AfmError, AfmVersion, Font, FontMetrics, Line, Lines, ParseOptions, Section, Warning,
+};
+use borrowed::HandWritten;
+use build::Builder;
+use read::read_with;
+
+/// The metrics of a composite font, read from an ACFM file.
+#[derive(PartialEq, Debug, Default)]
+pub struct CompositeFontMetrics {
+    /// The global font information, including `MappingScheme` and `EscChar`.
+    pub global_info: FontMetrics,
+    pub descendants: Vec<Descendant>,
+}
+
+/// A descendant font of a composite font and the range of codes it covers.
+#[derive(PartialEq, Debug, Default)]
+pub struct Descendant {
+    pub min_code: u32,
+    pub max_code: u32,
+    pub metrics: FontMetrics,
+}
+
+/// The mapping scheme of escape mapped composite fonts, in which `EscChar`
+/// switches the descendant instead of selecting a character.
+const ESCAPE_MAPPING: u32 = 3;
+
+fn code() -> Parser<u8, u32> {
+    uinteger() | hex_integer().map(|code| code as u32)
+}
+
+fn descendant<'a>(
+    lines: &mut Lines<'a>,
+    start: &Line,
+    version: AfmVersion,
+) -> Result<Descendant, AfmError> {
+    let range = code() - space() + code();
+    let (min_code, max_code) = keyword_value(start, range, Section::CompFontMetrics)?;
+    let mut font = Font::new(version, Builder::default());
+    block(lines, Section::Descendent, |lines, line| {
+        font_command(lines, line, Section::Descendent, &mut font, &HandWritten)
+    })?;
+    font.report_unknown_glyphs(lines);
+    Ok(Descendant {
+        min_code,
+        max_code,
+        metrics: font.into_metrics(),
+    })
+}
+
+fn composite_font_metrics(lines: &mut Lines) -> Result<CompositeFontMetrics, AfmError> {
+    let version = start_line(lines, Section::CompFontMetrics)?;
+
+    let mut metrics = CompositeFontMetrics::default();
+    let mut global_info = Font::new(version, Builder::default());
+    let mut descendants = None;
+    // The StartDescendent lines, counting descendants skipped in lenient
+    // mode.
+    let mut found = 0;
+    block(lines, Section::CompFontMetrics, |lines, line| {
+        let section = Section::CompFontMetrics;
+        let (column, keyword) = line.token(0);
+        match keyword {
+            b"Descendants" => {
+                let count = keyword_value(line, uinteger(), section)?;
+                descendants = Some((count, line.position(column)));
+            }
+            b"StartDescendent" => {
+                found += 1;
+                metrics.descendants.push(descendant(lines, line, version)?)
+            }
+            _ => font_command(lines, line, section, &mut global_info, &HandWritten)?,
+        }
+        Ok(())
+    })?;
+    end_of_input(lines, Section::CompFontMetrics)?;
+
+    check_count(lines, descendants, found, Section::Descendent)?;
+    global_info.report_unknown_glyphs(lines);
+    metrics.global_info = global_info.into_metrics();
+    Ok(metrics)
+}
+
+impl CompositeFontMetrics {
+    /// Parses the content of an ACFM file.
+    pub fn parse(input: &[u8]) -> Result<CompositeFontMetrics, AfmError> {
+        CompositeFontMetrics::parse_with(input, &ParseOptions::default())
+            .map(|(metrics, _)| metrics)
+    }
+
+    /// Parses the content of an ACFM file, also returning the problems
+    /// skipped in lenient mode.
+    pub fn parse_with(
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(CompositeFontMetrics, Vec<Warning>), AfmError> {
+        let lines = &mut Lines::new(input, options);
+        let metrics = composite_font_metrics(lines)?;
+        Ok((metrics, lines.warnings.split_off(0)))
+    }
+
+    /// Parses an ACFM file read from `reader` line by line, so that large
+    /// files are never held in memory as a whole. Parse errors are returned
+    /// as `InvalidData` wrapping the `AfmError`.
+    pub fn from_reader<R: Read>(reader: R) -> io::Result<CompositeFontMetrics> {
+        CompositeFontMetrics::from_reader_with(reader, &ParseOptions::default())
+            .map(|(metrics, _)| metrics)
+    }
+
+    /// Like `from_reader`, also returning the problems skipped in lenient
+    /// mode.
+    pub fn from_reader_with<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> io::Result<(CompositeFontMetrics, Vec<Warning>)> {
+        read_with(reader, options, composite_font_metrics)
+    }
+
+    /// Parses the ACFM file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<CompositeFontMetrics> {
+        CompositeFontMetrics::from_reader(File::open(path)?)
+    }
+
+    /// The descendant font covering `code`. With escape mapping, `EscChar`
+    /// itself does not belong to any descendant.
+    pub fn descendant(&self, code: u32) -> Option<&Descendant> {
+        let info = &self.global_info;
+        if info.mapping_scheme == ESCAPE_MAPPING && code == info.esc_char {
+            return None;
+        }
+        self.descendants
+            .iter()
+            .find(|d| d.min_code <= code && code <= d.max_code)
+    }
+}
+
+impl FromStr for CompositeFontMetrics {
+    type Err = AfmError;
+
+    fn from_str(input: &str) -> Result<CompositeFontMetrics, AfmError> {
+        CompositeFontMetrics::parse(input.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Position;
+
+    static ACFM: &[u8] = br#"StartCompFontMetrics 4.0
+Comment Generated for testing
+FontName Ryumin-Light-Ext-H
+FontBBox -170 -331 1024 903
+Version 001.002
+EncodingScheme FontSpecific
+MappingScheme 3
+EscChar 255
+CharacterSet Adobe-Japan1
+Characters 3
+IsBaseFont false
+VVector 500 880
+IsFixedV true
+Descendants 2
+StartDescendent 0 127
+FontName Ryumin-Light.Roman
+IsBaseFont true
+StartCharMetrics 2
+C 32 ; WX 250 ; N space ; B 0 0 0 0 ;
+C 33 ; WX 324 ; N exclam ; B 109 -14 215 724 ;
+EndCharMetrics
+EndDescendent
+StartDescendent <80> <FE>
+FontName Ryumin-Light.Kana
+StartDirection 0
+CharWidth 1000 0
+EndDirection
+StartCharMetrics 1
+C 161 ; WX 1000 ; N a ; B 0 0 1000 800 ;
+EndCharMetrics
+EndDescendent
+EndCompFontMetrics
+"#;
+
+    #[test]
+    fn parse_composite_font_metrics() {
+        let acfm = CompositeFontMetrics::parse(ACFM).unwrap();
+        assert_eq!(acfm.global_info.font_name, "Ryumin-Light-Ext-H");
+        assert_eq!(acfm.global_info.mapping_scheme, 3);
+        assert_eq!(acfm.global_info.esc_char, 255);
+        assert!(!acfm.global_info.is_base_font);
+        assert_eq!(acfm.descendants.len(), 2);
+
+        let roman = &acfm.descendants[0];
+        assert_eq!((roman.min_code, roman.max_code), (0, 127));
+        assert_eq!(roman.metrics.font_name, "Ryumin-Light.Roman");
+        assert_eq!(roman.metrics.char_metrics.len(), 2);
+
+        let kana = &acfm.descendants[1];
+        assert_eq!((kana.min_code, kana.max_code), (0x80, 0xFE));
+        assert_eq!(
+            kana.metrics.direction0.as_ref().unwrap().char_width,
+            (1000.0, 0.0)
+        );
+
+        assert_eq!(acfm.descendant(33), Some(roman));
+        assert_eq!(acfm.descendant(200), Some(kana));
+        assert_eq!(acfm.descendant(255), None);
+    }
+
+    #[test]
+    fn check_descendant_section() {
+        let input = b"StartCompFontMetrics 4.0
+Descendants 1
+StartDescendent 0 127
+FontName Ryumin-Light.Roman
+Masters 2
+EndDescendent
+EndCompFontMetrics
+";
+        assert_eq!(
+            CompositeFontMetrics::parse(input),
+            Err(AfmError::UnexpectedKeyword {
+                keyword: "Masters".to_string(),
+                section: Section::Descendent,
+                position: Position {
+                    line: 5,
+                    column: 1,
+                    offset: 89,
+                },
+            })
+        );
+    }
+}
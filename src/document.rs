@@ -0,0 +1,449 @@
+use pom::char_class;
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use borrowed::HandWritten;
+
+use super::{
+    token, AfmError, FontMetrics, FontMetricsRef, Grammar, Line, Lines, ParseOptions, Warning,
+    BYTE_ORDER_MARK,
+};
+
+/// A lossless concrete syntax tree of an AFM file.
+///
+/// Every line is kept as it is, including comments, blank lines, spacing
+/// and line breaks, so that `write_to` reproduces the input byte for byte.
+/// Edits rewrite only the lines they touch.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Document {
+    /// Whether the input starts with a UTF-8 byte order mark, which is not
+    /// part of the first line.
+    byte_order_mark: bool,
+    lines: Vec<DocumentLine>,
+    /// The options the document was parsed with, for `font_metrics`.
+    options: ParseOptions,
+}
+
+/// A line of a `Document`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct DocumentLine {
+    text: Vec<u8>,
+    line_break: Vec<u8>,
+}
+
+impl DocumentLine {
+    /// The content of the line without its line break.
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// The line break ending the line, empty for the last line of a file
+    /// without a final line break.
+    pub fn line_break(&self) -> &[u8] {
+        &self.line_break
+    }
+
+    /// The keyword the line starts with.
+    pub fn keyword(&self) -> &[u8] {
+        token(&self.text, 0).1
+    }
+
+    fn line(&self) -> Line<'_> {
+        Line {
+            number: 0,
+            offset: 0,
+            text: Cow::Borrowed(&self.text),
+        }
+    }
+}
+
+/// The section a line is part of, as far as edits tell them apart.
+#[derive(PartialEq, Clone, Copy)]
+enum Entries {
+    /// The top level of the file.
+    None,
+    CharMetrics,
+    KernPairs,
+    /// Any other section, e.g. `StartDirection` or `StartComposites`.
+    Other,
+}
+
+/// The start and end column of the value following `column`, without the
+/// surrounding spaces.
+fn value_span(text: &[u8], column: usize, end: usize) -> (usize, usize) {
+    let start = column
+        + text[column..end]
+            .iter()
+            .take_while(|&&c| char_class::space(c))
+            .count();
+    let len = text[start..end]
+        .iter()
+        .rev()
+        .skip_while(|&&c| char_class::space(c))
+        .count();
+    (start, start + len)
+}
+
+/// The columns of the tokens of `line`, as the parser splits them.
+fn tokens(line: &Line) -> Vec<(usize, usize)> {
+    let mut tokens = Vec::new();
+    let mut column = 0;
+    loop {
+        let (start, token) = line.token(column);
+        if token.is_empty() {
+            return tokens;
+        }
+        column = start + token.len();
+        tokens.push((start, column));
+    }
+}
+
+fn replace(text: &mut Vec<u8>, (start, end): (usize, usize), value: &str) {
+    text.splice(start..end, value.bytes());
+}
+
+impl Document {
+    /// Reads the lines of an AFM file, which must parse as `FontMetrics`.
+    pub fn parse(input: &[u8]) -> Result<Document, AfmError> {
+        Document::parse_with(input, &ParseOptions::default()).map(|(document, _)| document)
+    }
+
+    /// Reads the lines of an AFM file, which must parse as `FontMetrics`
+    /// with `options`, also returning the problems skipped in lenient mode.
+    /// The lines are kept as they are, including those skipped.
+    pub fn parse_with(
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Document, Vec<Warning>), AfmError> {
+        let (_, warnings) = FontMetricsRef::parse_with(input, options)?;
+        let lines = Lines::new(input, options).map(|line| {
+            let rest = &input[line.offset + line.text.len()..];
+            let line_break = if rest.starts_with(b"\r\n") {
+                &rest[..2]
+            } else {
+                &rest[..rest.len().min(1)]
+            };
+            DocumentLine {
+                text: line.text.to_vec(),
+                line_break: line_break.to_vec(),
+            }
+        });
+        let document = Document {
+            byte_order_mark: input.starts_with(BYTE_ORDER_MARK),
+            lines: lines.collect(),
+            options: *options,
+        };
+        Ok((document, warnings))
+    }
+
+    pub fn lines(&self) -> &[DocumentLine] {
+        &self.lines
+    }
+
+    /// The metrics described by the current content of the document,
+    /// parsed with the options it was read with.
+    pub fn font_metrics(&self) -> Result<FontMetrics, AfmError> {
+        let bytes = self.to_bytes();
+        FontMetrics::parse_with(&bytes, &self.options).map(|(metrics, _)| metrics)
+    }
+
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.byte_order_mark {
+            out.write_all(BYTE_ORDER_MARK)?;
+        }
+        for line in &self.lines {
+            out.write_all(&line.text)?;
+            out.write_all(&line.line_break)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if self.byte_order_mark {
+            bytes.extend_from_slice(BYTE_ORDER_MARK);
+        }
+        for line in &self.lines {
+            bytes.extend_from_slice(&line.text);
+            bytes.extend_from_slice(&line.line_break);
+        }
+        bytes
+    }
+
+    /// The lines together with the innermost section they belong to,
+    /// leaving out the lines starting and ending sections. Like the parser,
+    /// takes every keyword starting with `End` to end a section.
+    fn entries(&mut self) -> Vec<(Entries, &mut DocumentLine)> {
+        let mut sections = Vec::new();
+        let mut lines = Vec::new();
+        for line in &mut self.lines {
+            let keyword = line.keyword();
+            if keyword == b"StartFontMetrics" || keyword == b"EndFontMetrics" {
+                lines.push((Entries::None, line));
+            } else if keyword.starts_with(b"Start") {
+                sections.push(match keyword {
+                    b"StartCharMetrics" => Entries::CharMetrics,
+                    b"StartKernPairs" | b"StartKernPairs0" | b"StartKernPairs1" => {
+                        Entries::KernPairs
+                    }
+                    _ => Entries::Other,
+                });
+            } else if keyword.starts_with(b"End") {
+                sections.pop();
+            } else {
+                let entries = sections.last().cloned().unwrap_or(Entries::None);
+                lines.push((entries, line));
+            }
+        }
+        lines
+    }
+
+    /// Replaces the value of the first top level line starting with
+    /// `keyword`, e.g. `FontName`, skipping lines inside any section such
+    /// as `StartDirection`. Returns whether such a line was found. A value
+    /// with a line break is refused, leaving the document unchanged.
+    pub fn set_value(&mut self, keyword: &str, value: &str) -> bool {
+        if value.contains(['\r', '\n']) {
+            return false;
+        }
+        for (entries, line) in self.entries() {
+            if entries != Entries::None || line.keyword() != keyword.as_bytes() {
+                continue;
+            }
+            let column = line.line().token(0).0 + keyword.len();
+            let end = line.text.len();
+            let span = value_span(&line.text, column, end);
+            if span.0 == column {
+                // Keep the value apart from the keyword.
+                replace(&mut line.text, span, &format!(" {}", value));
+            } else {
+                replace(&mut line.text, span, value);
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Sets the kerning of the first pair of `first` and `second`. Only the
+    /// changed numbers are rewritten, unless the key of the line cannot hold
+    /// them, e.g. a `KPX` line getting a vertical component. Returns whether
+    /// the pair was found. Numbers that are not finite are refused.
+    pub fn set_kern_pair(&mut self, first: &str, second: &str, x: f64, y: f64) -> bool {
+        if !x.is_finite() || !y.is_finite() {
+            return false;
+        }
+        for (entries, line) in self.entries() {
+            if entries != Entries::KernPairs {
+                continue;
+            }
+            let column = line.line().token(0).0;
+            let found = match HandWritten.kern_pair(&line.line()) {
+                Ok(pair) => {
+                    pair.first_kern_character == first && pair.second_kern_character == second
+                }
+                Err(_) => false,
+            };
+            if !found {
+                continue;
+            }
+            let words = tokens(&line.line());
+            let keyword = line.keyword().to_vec();
+            match &keyword[..] {
+                b"KPX" if y == 0.0 => replace(&mut line.text, words[3], &x.to_string()),
+                b"KPY" if x == 0.0 => replace(&mut line.text, words[3], &y.to_string()),
+                b"KP" | b"KPH" => {
+                    replace(&mut line.text, words[4], &y.to_string());
+                    replace(&mut line.text, words[3], &x.to_string());
+                }
+                _ => {
+                    let pair = format!("KP {} {} {} {}", first, second, x, y);
+                    replace(&mut line.text, (column, words[words.len() - 1].1), &pair);
+                }
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Sets the `WX` width of the glyph named `name`, adding the key after
+    /// the character code if the line has none. Returns whether the glyph
+    /// was found. A width that is not finite is refused.
+    pub fn set_char_width(&mut self, name: &str, wx: f64) -> bool {
+        if !wx.is_finite() {
+            return false;
+        }
+        // Only the name is needed, so keys that fail to parse are skipped.
+        let lenient = ParseOptions {
+            strict: false,
+            ..self.options
+        };
+        for (entries, line) in self.entries() {
+            if entries != Entries::CharMetrics {
+                continue;
+            }
+            let mut segments = Vec::new();
+            let mut start = 0;
+            for segment in line.text.split(|&c| c == b';') {
+                segments.push((start, start + segment.len()));
+                start += segment.len() + 1;
+            }
+            let mut lines = Lines::new(b"", &lenient);
+            let metric = HandWritten.char_metric(&mut lines, &line.line());
+            if !metric.is_ok_and(|metric| metric.name == name) {
+                continue;
+            }
+            for &(start, end) in &segments {
+                let (column, keyword) = token(&line.text, start);
+                if keyword == b"WX" {
+                    let span = value_span(&line.text, column + keyword.len(), end);
+                    replace(&mut line.text, span, &wx.to_string());
+                    return true;
+                }
+            }
+            let end = segments[0].1;
+            replace(&mut line.text, (end, end), &format!("; WX {} ", wx));
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static AFM: &[u8] = b"StartFontMetrics 4.1\r
+Comment  Spacing and comments are kept\r
+FontName   Test\r
+\r
+StartCharMetrics 4\r
+C 32 ; WX 250 ; N space ; B 0 0 0 0 ;\r
+C 65 ;  N A ; B 0 0 600 700 ;\r
+C 86 ; WX 667 ; N V ; B 0 0 660 700 ;\r
+C 87 ; WX 944 ; N W ; B 0 0 940 700 ;\r
+EndCharMetrics\r
+StartKernData\r
+StartKernPairs 2\r
+KPX A V  -80\r
+KPX A W -40\r
+EndKernPairs\r
+EndKernData\r
+EndFontMetrics";
+
+    #[test]
+    fn write_unchanged() {
+        let document = Document::parse(AFM).unwrap();
+        assert_eq!(document.lines().len(), 17);
+        assert_eq!(document.lines()[4].keyword(), b"StartCharMetrics");
+        assert_eq!(document.lines()[4].line_break(), b"\r\n");
+        assert_eq!(document.lines()[16].line_break(), b"");
+        assert_eq!(document.to_bytes(), AFM);
+
+        let input = b"\xEF\xBB\xBFStartFontMetrics 4.1\rNotice \xA9 Test\rEndFontMetrics\r";
+        let document = Document::parse(input).unwrap();
+        assert_eq!(document.lines()[0].keyword(), b"StartFontMetrics");
+        assert_eq!(document.lines()[1].line_break(), b"\r");
+        assert_eq!(document.to_bytes(), &input[..]);
+    }
+
+    #[test]
+    fn edit_lines() {
+        let mut document = Document::parse(AFM).unwrap();
+        assert!(document.set_value("FontName", "Renamed"));
+        assert!(document.set_char_width("space", 260.0));
+        assert!(document.set_char_width("A", 600.0));
+        assert!(document.set_kern_pair("A", "V", -75.0, 0.0));
+        assert!(document.set_kern_pair("A", "W", -40.0, 10.0));
+        assert!(!document.set_kern_pair("A", "Y", -40.0, 0.0));
+
+        let text: Vec<&[u8]> = document.lines().iter().map(|line| line.text()).collect();
+        assert_eq!(text[2], &b"FontName   Renamed"[..]);
+        assert_eq!(text[5], &b"C 32 ; WX 260 ; N space ; B 0 0 0 0 ;"[..]);
+        assert_eq!(text[6], &b"C 65 ; WX 600 ;  N A ; B 0 0 600 700 ;"[..]);
+        assert_eq!(text[12], &b"KPX A V  -75"[..]);
+        assert_eq!(text[13], &b"KP A W -40 10"[..]);
+
+        let metrics = document.font_metrics().unwrap();
+        assert_eq!(metrics.font_name, "Renamed");
+        assert_eq!(metrics.char_metrics[1].wx, 600.0);
+        assert_eq!(metrics.kern_pairs[1].y, 10.0);
+    }
+
+    #[test]
+    fn edit_lenient_input() {
+        let input = b"StartFontMetrics 4.1
+FontName Test
+Weight
+StartCharMetrics 2
+C 65 ; WX 500 ; N A ; B 0 0 abc 700 ;
+C 86 ; WX 600 ; N V ;
+EndCharMetrics
+StartKernData
+StartKernPairs 1
+KPX A V -80
+EndKernPairs
+EndKernData
+EndFontMetrics
+";
+        assert!(Document::parse(input).is_err());
+        let options = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        let (mut document, warnings) = Document::parse_with(input, &options).unwrap();
+        let lines: Vec<_> = warnings
+            .iter()
+            .map(|warning| warning.position().line)
+            .collect();
+        assert_eq!(lines, vec![3, 5]);
+        assert!(document.set_char_width("A", 550.0));
+        assert!(document.set_kern_pair("A", "V", -70.0, 0.0));
+        assert_eq!(
+            document.lines()[4].text(),
+            b"C 65 ; WX 550 ; N A ; B 0 0 abc 700 ;"
+        );
+        let metrics = document.font_metrics().unwrap();
+        assert_eq!(metrics.char_metrics[0].wx, 550.0);
+        assert_eq!(metrics.kern_pairs[0].x, -70.0);
+
+        // Values that would not read back are refused.
+        let before = document.to_bytes();
+        assert!(!document.set_value("FontName", "Two\nLines"));
+        assert!(!document.set_value("FontName", "Two\rLines"));
+        assert!(!document.set_char_width("A", f64::NAN));
+        assert!(!document.set_kern_pair("A", "V", f64::INFINITY, 0.0));
+        assert_eq!(document.to_bytes(), before);
+    }
+
+    #[test]
+    fn set_only_top_level_values() {
+        let input = b"StartFontMetrics 4.1
+FontName Test
+MetricsSets 2
+StartDirection 1
+ItalicAngle -12
+EndDirection
+StartCharMetrics 1
+C 65 ; WX 500 ; N A ;
+EndCharMetrics
+StartKernData
+StartTrackKern 1
+TrackKern 0 6 0 72 0
+EndTrackKern
+EndKernData
+StartComposites 1
+CC A 1 ; PCC A 0 0 ;
+EndComposites
+EndFontMetrics
+";
+        let mut document = Document::parse(input).unwrap();
+        assert!(!document.set_value("ItalicAngle", "0"));
+        assert!(!document.set_value("TrackKern", "1 6 0 72 0"));
+        assert!(!document.set_value("CC", "B 1"));
+        assert!(!document.set_value("StartKernData", "1"));
+        assert_eq!(document.to_bytes(), &input[..]);
+
+        assert!(document.set_value("MetricsSets", "1"));
+        assert_eq!(document.lines()[2].text(), b"MetricsSets 1");
+    }
+}
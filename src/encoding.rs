@@ -0,0 +1,493 @@
+use std::fmt;
+
+use super::{CharMetric, FontMetrics, GlyphId};
+
+/// An encoding vector, giving the codes 0 to 255 of a simple font the
+/// names of glyphs. These are the encodings of PDF's Appendix D and the
+/// built-in encodings of the Symbol and ZapfDingbats fonts.
+///
+/// ```
+/// # use afm::{Encoding, Standard14};
+/// let metrics = Standard14::TimesRoman.metrics();
+/// let encoded = metrics.encoded(Encoding::WinAnsi);
+/// assert_eq!(encoded.glyph(0x93).unwrap().name, "quotedblleft");
+/// assert_eq!(metrics.glyph_by_code(0x93), None);
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub enum Encoding {
+    /// `StandardEncoding`, the built-in encoding of Adobe's Latin fonts,
+    /// which their AFM files name `AdobeStandardEncoding`.
+    Standard,
+    /// `WinAnsiEncoding`, Windows code page 1252.
+    WinAnsi,
+    /// `MacRomanEncoding`, Mac OS Roman without the glyphs Latin fonts
+    /// take from the Symbol font, such as `notequal`, and with `currency`
+    /// in place of `Euro`.
+    MacRoman,
+    /// `MacExpertEncoding`, for expert fonts with small capitals, old style
+    /// figures and fractions.
+    MacExpert,
+    /// `PDFDocEncoding`, the encoding of text strings outside content
+    /// streams.
+    PdfDoc,
+    /// The built-in encoding of the Symbol font.
+    Symbol,
+    /// The built-in encoding of the ZapfDingbats font.
+    ZapfDingbats,
+}
+
+impl Encoding {
+    /// Every encoding, in the order of the variants.
+    pub const ALL: [Encoding; 7] = [
+        Encoding::Standard,
+        Encoding::WinAnsi,
+        Encoding::MacRoman,
+        Encoding::MacExpert,
+        Encoding::PdfDoc,
+        Encoding::Symbol,
+        Encoding::ZapfDingbats,
+    ];
+
+    /// The name of the encoding, such as `WinAnsiEncoding`. PDF has no
+    /// names for the encodings of Symbol and ZapfDingbats, which are
+    /// `SymbolEncoding` and `ZapfDingbatsEncoding` here.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Standard => "StandardEncoding",
+            Encoding::WinAnsi => "WinAnsiEncoding",
+            Encoding::MacRoman => "MacRomanEncoding",
+            Encoding::MacExpert => "MacExpertEncoding",
+            Encoding::PdfDoc => "PDFDocEncoding",
+            Encoding::Symbol => "SymbolEncoding",
+            Encoding::ZapfDingbats => "ZapfDingbatsEncoding",
+        }
+    }
+
+    /// The encoding named `name`, which may also be the
+    /// `AdobeStandardEncoding` of `FontMetrics::encoding_scheme`.
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        if name == "AdobeStandardEncoding" {
+            return Some(Encoding::Standard);
+        }
+        let mut encodings = Encoding::ALL.iter();
+        encodings.find(|encoding| encoding.name() == name).cloned()
+    }
+
+    fn table(self) -> &'static [&'static str; 256] {
+        match self {
+            Encoding::Standard => &STANDARD,
+            Encoding::WinAnsi => &WIN_ANSI,
+            Encoding::MacRoman => &MAC_ROMAN,
+            Encoding::MacExpert => &MAC_EXPERT,
+            Encoding::PdfDoc => &PDF_DOC,
+            Encoding::Symbol => &SYMBOL,
+            Encoding::ZapfDingbats => &ZAPF_DINGBATS,
+        }
+    }
+
+    /// The name of the glyph the encoding gives `code`.
+    pub fn glyph_name(self, code: u8) -> Option<&'static str> {
+        Some(self.table()[code as usize]).filter(|name| !name.is_empty())
+    }
+
+    /// The lowest code of the glyph named `name`. A few glyphs have more
+    /// than one, such as `space` at 32 and 160 in `WinAnsiEncoding`.
+    pub fn code(self, name: &str) -> Option<u8> {
+        let i = self
+            .table()
+            .iter()
+            .position(|&entry| entry == name && !name.is_empty())?;
+        Some(i as u8)
+    }
+
+    /// The codes the encoding gives a glyph, with the names of the glyphs.
+    pub fn glyph_names(self) -> impl Iterator<Item = (u8, &'static str)> {
+        let codes = self.table().iter().enumerate();
+        codes
+            .filter(|&(_, name)| !name.is_empty())
+            .map(|(code, &name)| (code as u8, name))
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// `FontMetrics` seen through an `Encoding`, which finds the glyph of a
+/// code by the name the encoding gives it rather than by the
+/// `character_code` of the font's built-in encoding.
+#[derive(Clone, Copy, Debug)]
+pub struct EncodedFontMetrics<'a> {
+    metrics: &'a FontMetrics,
+    encoding: Encoding,
+}
+
+impl<'a> EncodedFontMetrics<'a> {
+    pub fn metrics(&self) -> &'a FontMetrics {
+        self.metrics
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The id of the glyph of `code`, if the encoding names one and the
+    /// font has it.
+    pub fn glyph_id(&self, code: u8) -> Option<GlyphId> {
+        self.metrics.glyph_id(self.encoding.glyph_name(code)?)
+    }
+
+    /// The char metrics of the glyph of `code`.
+    pub fn glyph(&self, code: u8) -> Option<&'a CharMetric> {
+        self.glyph_id(code).and_then(|id| self.metrics.glyph(id))
+    }
+
+    /// The codes whose glyphs the font has, with their char metrics, in the
+    /// order of the codes.
+    pub fn glyphs(&self) -> impl Iterator<Item = (u8, &'a CharMetric)> + 'a {
+        let encoded = *self;
+        let codes = encoded.encoding.glyph_names();
+        codes.filter_map(move |(code, name)| Some((code, encoded.metrics.glyph_by_name(name)?)))
+    }
+}
+
+impl FontMetrics {
+    /// Views the metrics through `encoding`, to look glyphs up by the codes
+    /// it gives them.
+    pub fn encoded(&self, encoding: Encoding) -> EncodedFontMetrics<'_> {
+        EncodedFontMetrics {
+            metrics: self,
+            encoding,
+        }
+    }
+}
+
+// The glyph names of the codes, where an empty name leaves a code
+// unencoded. The Latin encodings are those of Appendix D of the PDF
+// reference, the others those of the AFM files of Symbol and ZapfDingbats.
+
+#[rustfmt::skip]
+static STANDARD: [&str; 256] = [
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quoteright",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash",
+    "zero", "one", "two", "three", "four", "five", "six", "seven",
+    "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question",
+    "at", "A", "B", "C", "D", "E", "F", "G",
+    "H", "I", "J", "K", "L", "M", "N", "O",
+    "P", "Q", "R", "S", "T", "U", "V", "W",
+    "X", "Y", "Z", "bracketleft", "backslash", "bracketright", "asciicircum", "underscore",
+    "quoteleft", "a", "b", "c", "d", "e", "f", "g",
+    "h", "i", "j", "k", "l", "m", "n", "o",
+    "p", "q", "r", "s", "t", "u", "v", "w",
+    "x", "y", "z", "braceleft", "bar", "braceright", "asciitilde", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "exclamdown", "cent", "sterling", "fraction", "yen", "florin", "section",
+    "currency", "quotesingle", "quotedblleft", "guillemotleft", "guilsinglleft", "guilsinglright", "fi", "fl",
+    "", "endash", "dagger", "daggerdbl", "periodcentered", "", "paragraph", "bullet",
+    "quotesinglbase", "quotedblbase", "quotedblright", "guillemotright", "ellipsis", "perthousand", "", "questiondown",
+    "", "grave", "acute", "circumflex", "tilde", "macron", "breve", "dotaccent",
+    "dieresis", "", "ring", "cedilla", "", "hungarumlaut", "ogonek", "caron",
+    "emdash", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "AE", "", "ordfeminine", "", "", "", "",
+    "Lslash", "Oslash", "OE", "ordmasculine", "", "", "", "",
+    "", "ae", "", "", "", "dotlessi", "", "",
+    "lslash", "oslash", "oe", "germandbls", "", "", "", "",
+];
+
+#[rustfmt::skip]
+static WIN_ANSI: [&str; 256] = [
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quotesingle",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash",
+    "zero", "one", "two", "three", "four", "five", "six", "seven",
+    "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question",
+    "at", "A", "B", "C", "D", "E", "F", "G",
+    "H", "I", "J", "K", "L", "M", "N", "O",
+    "P", "Q", "R", "S", "T", "U", "V", "W",
+    "X", "Y", "Z", "bracketleft", "backslash", "bracketright", "asciicircum", "underscore",
+    "grave", "a", "b", "c", "d", "e", "f", "g",
+    "h", "i", "j", "k", "l", "m", "n", "o",
+    "p", "q", "r", "s", "t", "u", "v", "w",
+    "x", "y", "z", "braceleft", "bar", "braceright", "asciitilde", "",
+    "Euro", "", "quotesinglbase", "florin", "quotedblbase", "ellipsis", "dagger", "daggerdbl",
+    "circumflex", "perthousand", "Scaron", "guilsinglleft", "OE", "", "Zcaron", "",
+    "", "quoteleft", "quoteright", "quotedblleft", "quotedblright", "bullet", "endash", "emdash",
+    "tilde", "trademark", "scaron", "guilsinglright", "oe", "", "zcaron", "Ydieresis",
+    "space", "exclamdown", "cent", "sterling", "currency", "yen", "brokenbar", "section",
+    "dieresis", "copyright", "ordfeminine", "guillemotleft", "logicalnot", "hyphen", "registered", "macron",
+    "degree", "plusminus", "twosuperior", "threesuperior", "acute", "mu", "paragraph", "periodcentered",
+    "cedilla", "onesuperior", "ordmasculine", "guillemotright", "onequarter", "onehalf", "threequarters", "questiondown",
+    "Agrave", "Aacute", "Acircumflex", "Atilde", "Adieresis", "Aring", "AE", "Ccedilla",
+    "Egrave", "Eacute", "Ecircumflex", "Edieresis", "Igrave", "Iacute", "Icircumflex", "Idieresis",
+    "Eth", "Ntilde", "Ograve", "Oacute", "Ocircumflex", "Otilde", "Odieresis", "multiply",
+    "Oslash", "Ugrave", "Uacute", "Ucircumflex", "Udieresis", "Yacute", "Thorn", "germandbls",
+    "agrave", "aacute", "acircumflex", "atilde", "adieresis", "aring", "ae", "ccedilla",
+    "egrave", "eacute", "ecircumflex", "edieresis", "igrave", "iacute", "icircumflex", "idieresis",
+    "eth", "ntilde", "ograve", "oacute", "ocircumflex", "otilde", "odieresis", "divide",
+    "oslash", "ugrave", "uacute", "ucircumflex", "udieresis", "yacute", "thorn", "ydieresis",
+];
+
+#[rustfmt::skip]
+static MAC_ROMAN: [&str; 256] = [
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quotesingle",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash",
+    "zero", "one", "two", "three", "four", "five", "six", "seven",
+    "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question",
+    "at", "A", "B", "C", "D", "E", "F", "G",
+    "H", "I", "J", "K", "L", "M", "N", "O",
+    "P", "Q", "R", "S", "T", "U", "V", "W",
+    "X", "Y", "Z", "bracketleft", "backslash", "bracketright", "asciicircum", "underscore",
+    "grave", "a", "b", "c", "d", "e", "f", "g",
+    "h", "i", "j", "k", "l", "m", "n", "o",
+    "p", "q", "r", "s", "t", "u", "v", "w",
+    "x", "y", "z", "braceleft", "bar", "braceright", "asciitilde", "",
+    "Adieresis", "Aring", "Ccedilla", "Eacute", "Ntilde", "Odieresis", "Udieresis", "aacute",
+    "agrave", "acircumflex", "adieresis", "atilde", "aring", "ccedilla", "eacute", "egrave",
+    "ecircumflex", "edieresis", "iacute", "igrave", "icircumflex", "idieresis", "ntilde", "oacute",
+    "ograve", "ocircumflex", "odieresis", "otilde", "uacute", "ugrave", "ucircumflex", "udieresis",
+    "dagger", "degree", "cent", "sterling", "section", "bullet", "paragraph", "germandbls",
+    "registered", "copyright", "trademark", "acute", "dieresis", "", "AE", "Oslash",
+    "", "plusminus", "", "", "yen", "mu", "", "",
+    "", "", "", "ordfeminine", "ordmasculine", "", "ae", "oslash",
+    "questiondown", "exclamdown", "logicalnot", "", "florin", "", "", "guillemotleft",
+    "guillemotright", "ellipsis", "space", "Agrave", "Atilde", "Otilde", "OE", "oe",
+    "endash", "emdash", "quotedblleft", "quotedblright", "quoteleft", "quoteright", "divide", "",
+    "ydieresis", "Ydieresis", "fraction", "currency", "guilsinglleft", "guilsinglright", "fi", "fl",
+    "daggerdbl", "periodcentered", "quotesinglbase", "quotedblbase", "perthousand", "Acircumflex", "Ecircumflex", "Aacute",
+    "Edieresis", "Egrave", "Iacute", "Icircumflex", "Idieresis", "Igrave", "Oacute", "Ocircumflex",
+    "", "Ograve", "Uacute", "Ucircumflex", "Ugrave", "dotlessi", "circumflex", "tilde",
+    "macron", "breve", "dotaccent", "ring", "cedilla", "hungarumlaut", "ogonek", "caron",
+];
+
+#[rustfmt::skip]
+static MAC_EXPERT: [&str; 256] = [
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "space", "exclamsmall", "Hungarumlautsmall", "centoldstyle", "dollaroldstyle", "dollarsuperior", "ampersandsmall", "Acutesmall",
+    "parenleftsuperior", "parenrightsuperior", "twodotenleader", "onedotenleader", "comma", "hyphen", "period", "fraction",
+    "zerooldstyle", "oneoldstyle", "twooldstyle", "threeoldstyle", "fouroldstyle", "fiveoldstyle", "sixoldstyle", "sevenoldstyle",
+    "eightoldstyle", "nineoldstyle", "colon", "semicolon", "", "threequartersemdash", "", "questionsmall",
+    "", "", "", "", "Ethsmall", "", "", "onequarter",
+    "onehalf", "threequarters", "oneeighth", "threeeighths", "fiveeighths", "seveneighths", "onethird", "twothirds",
+    "", "", "", "", "", "", "ff", "fi",
+    "fl", "ffi", "ffl", "parenleftinferior", "", "parenrightinferior", "Circumflexsmall", "hypheninferior",
+    "Gravesmall", "Asmall", "Bsmall", "Csmall", "Dsmall", "Esmall", "Fsmall", "Gsmall",
+    "Hsmall", "Ismall", "Jsmall", "Ksmall", "Lsmall", "Msmall", "Nsmall", "Osmall",
+    "Psmall", "Qsmall", "Rsmall", "Ssmall", "Tsmall", "Usmall", "Vsmall", "Wsmall",
+    "Xsmall", "Ysmall", "Zsmall", "colonmonetary", "onefitted", "rupiah", "Tildesmall", "",
+    "", "asuperior", "centsuperior", "", "", "", "", "Aacutesmall",
+    "Agravesmall", "Acircumflexsmall", "Adieresissmall", "Atildesmall", "Aringsmall", "Ccedillasmall", "Eacutesmall", "Egravesmall",
+    "Ecircumflexsmall", "Edieresissmall", "Iacutesmall", "Igravesmall", "Icircumflexsmall", "Idieresissmall", "Ntildesmall", "Oacutesmall",
+    "Ogravesmall", "Ocircumflexsmall", "Odieresissmall", "Otildesmall", "Uacutesmall", "Ugravesmall", "Ucircumflexsmall", "Udieresissmall",
+    "", "eightsuperior", "fourinferior", "threeinferior", "sixinferior", "eightinferior", "seveninferior", "Scaronsmall",
+    "", "centinferior", "twoinferior", "", "Dieresissmall", "", "Caronsmall", "osuperior",
+    "fiveinferior", "", "commainferior", "periodinferior", "Yacutesmall", "", "dollarinferior", "",
+    "", "Thornsmall", "", "nineinferior", "zeroinferior", "Zcaronsmall", "AEsmall", "Oslashsmall",
+    "questiondownsmall", "oneinferior", "Lslashsmall", "", "", "", "", "",
+    "", "Cedillasmall", "", "", "", "", "", "OEsmall",
+    "figuredash", "hyphensuperior", "", "", "", "", "exclamdownsmall", "",
+    "Ydieresissmall", "", "onesuperior", "twosuperior", "threesuperior", "foursuperior", "fivesuperior", "sixsuperior",
+    "sevensuperior", "ninesuperior", "zerosuperior", "", "esuperior", "rsuperior", "tsuperior", "",
+    "", "isuperior", "ssuperior", "dsuperior", "", "", "", "",
+    "", "lsuperior", "Ogoneksmall", "Brevesmall", "Macronsmall", "bsuperior", "nsuperior", "msuperior",
+    "commasuperior", "periodsuperior", "Dotaccentsmall", "Ringsmall", "", "", "", "",
+];
+
+#[rustfmt::skip]
+static PDF_DOC: [&str; 256] = [
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "breve", "caron", "circumflex", "dotaccent", "hungarumlaut", "ogonek", "ring", "tilde",
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quotesingle",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash",
+    "zero", "one", "two", "three", "four", "five", "six", "seven",
+    "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question",
+    "at", "A", "B", "C", "D", "E", "F", "G",
+    "H", "I", "J", "K", "L", "M", "N", "O",
+    "P", "Q", "R", "S", "T", "U", "V", "W",
+    "X", "Y", "Z", "bracketleft", "backslash", "bracketright", "asciicircum", "underscore",
+    "grave", "a", "b", "c", "d", "e", "f", "g",
+    "h", "i", "j", "k", "l", "m", "n", "o",
+    "p", "q", "r", "s", "t", "u", "v", "w",
+    "x", "y", "z", "braceleft", "bar", "braceright", "asciitilde", "",
+    "bullet", "dagger", "daggerdbl", "ellipsis", "emdash", "endash", "florin", "fraction",
+    "guilsinglleft", "guilsinglright", "minus", "perthousand", "quotedblbase", "quotedblleft", "quotedblright", "quoteleft",
+    "quoteright", "quotesinglbase", "trademark", "fi", "fl", "Lslash", "OE", "Scaron",
+    "Ydieresis", "Zcaron", "dotlessi", "lslash", "oe", "scaron", "zcaron", "",
+    "Euro", "exclamdown", "cent", "sterling", "currency", "yen", "brokenbar", "section",
+    "dieresis", "copyright", "ordfeminine", "guillemotleft", "logicalnot", "", "registered", "macron",
+    "degree", "plusminus", "twosuperior", "threesuperior", "acute", "mu", "paragraph", "periodcentered",
+    "cedilla", "onesuperior", "ordmasculine", "guillemotright", "onequarter", "onehalf", "threequarters", "questiondown",
+    "Agrave", "Aacute", "Acircumflex", "Atilde", "Adieresis", "Aring", "AE", "Ccedilla",
+    "Egrave", "Eacute", "Ecircumflex", "Edieresis", "Igrave", "Iacute", "Icircumflex", "Idieresis",
+    "Eth", "Ntilde", "Ograve", "Oacute", "Ocircumflex", "Otilde", "Odieresis", "multiply",
+    "Oslash", "Ugrave", "Uacute", "Ucircumflex", "Udieresis", "Yacute", "Thorn", "germandbls",
+    "agrave", "aacute", "acircumflex", "atilde", "adieresis", "aring", "ae", "ccedilla",
+    "egrave", "eacute", "ecircumflex", "edieresis", "igrave", "iacute", "icircumflex", "idieresis",
+    "eth", "ntilde", "ograve", "oacute", "ocircumflex", "otilde", "odieresis", "divide",
+    "oslash", "ugrave", "uacute", "ucircumflex", "udieresis", "yacute", "thorn", "ydieresis",
+];
+
+#[rustfmt::skip]
+static SYMBOL: [&str; 256] = [
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "space", "exclam", "universal", "numbersign", "existential", "percent", "ampersand", "suchthat",
+    "parenleft", "parenright", "asteriskmath", "plus", "comma", "minus", "period", "slash",
+    "zero", "one", "two", "three", "four", "five", "six", "seven",
+    "eight", "nine", "colon", "semicolon", "less", "equal", "greater", "question",
+    "congruent", "Alpha", "Beta", "Chi", "Delta", "Epsilon", "Phi", "Gamma",
+    "Eta", "Iota", "theta1", "Kappa", "Lambda", "Mu", "Nu", "Omicron",
+    "Pi", "Theta", "Rho", "Sigma", "Tau", "Upsilon", "sigma1", "Omega",
+    "Xi", "Psi", "Zeta", "bracketleft", "therefore", "bracketright", "perpendicular", "underscore",
+    "radicalex", "alpha", "beta", "chi", "delta", "epsilon", "phi", "gamma",
+    "eta", "iota", "phi1", "kappa", "lambda", "mu", "nu", "omicron",
+    "pi", "theta", "rho", "sigma", "tau", "upsilon", "omega1", "omega",
+    "xi", "psi", "zeta", "braceleft", "bar", "braceright", "similar", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "Euro", "Upsilon1", "minute", "lessequal", "fraction", "infinity", "florin", "club",
+    "diamond", "heart", "spade", "arrowboth", "arrowleft", "arrowup", "arrowright", "arrowdown",
+    "degree", "plusminus", "second", "greaterequal", "multiply", "proportional", "partialdiff", "bullet",
+    "divide", "notequal", "equivalence", "approxequal", "ellipsis", "arrowvertex", "arrowhorizex", "carriagereturn",
+    "aleph", "Ifraktur", "Rfraktur", "weierstrass", "circlemultiply", "circleplus", "emptyset", "intersection",
+    "union", "propersuperset", "reflexsuperset", "notsubset", "propersubset", "reflexsubset", "element", "notelement",
+    "angle", "gradient", "registerserif", "copyrightserif", "trademarkserif", "product", "radical", "dotmath",
+    "logicalnot", "logicaland", "logicalor", "arrowdblboth", "arrowdblleft", "arrowdblup", "arrowdblright", "arrowdbldown",
+    "lozenge", "angleleft", "registersans", "copyrightsans", "trademarksans", "summation", "parenlefttp", "parenleftex",
+    "parenleftbt", "bracketlefttp", "bracketleftex", "bracketleftbt", "bracelefttp", "braceleftmid", "braceleftbt", "braceex",
+    "", "angleright", "integral", "integraltp", "integralex", "integralbt", "parenrighttp", "parenrightex",
+    "parenrightbt", "bracketrighttp", "bracketrightex", "bracketrightbt", "bracerighttp", "bracerightmid", "bracerightbt", "",
+];
+
+#[rustfmt::skip]
+static ZAPF_DINGBATS: [&str; 256] = [
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "space", "a1", "a2", "a202", "a3", "a4", "a5", "a119",
+    "a118", "a117", "a11", "a12", "a13", "a14", "a15", "a16",
+    "a105", "a17", "a18", "a19", "a20", "a21", "a22", "a23",
+    "a24", "a25", "a26", "a27", "a28", "a6", "a7", "a8",
+    "a9", "a10", "a29", "a30", "a31", "a32", "a33", "a34",
+    "a35", "a36", "a37", "a38", "a39", "a40", "a41", "a42",
+    "a43", "a44", "a45", "a46", "a47", "a48", "a49", "a50",
+    "a51", "a52", "a53", "a54", "a55", "a56", "a57", "a58",
+    "a59", "a60", "a61", "a62", "a63", "a64", "a65", "a66",
+    "a67", "a68", "a69", "a70", "a71", "a72", "a73", "a74",
+    "a203", "a75", "a204", "a76", "a77", "a78", "a79", "a81",
+    "a82", "a83", "a84", "a97", "a98", "a99", "a100", "",
+    "a89", "a90", "a93", "a94", "a91", "a92", "a205", "a85",
+    "a206", "a86", "a87", "a88", "a95", "a96", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "", "", "", "", "", "", "",
+    "", "a101", "a102", "a103", "a104", "a106", "a107", "a108",
+    "a112", "a111", "a110", "a109", "a120", "a121", "a122", "a123",
+    "a124", "a125", "a126", "a127", "a128", "a129", "a130", "a131",
+    "a132", "a133", "a134", "a135", "a136", "a137", "a138", "a139",
+    "a140", "a141", "a142", "a143", "a144", "a145", "a146", "a147",
+    "a148", "a149", "a150", "a151", "a152", "a153", "a154", "a155",
+    "a156", "a157", "a158", "a159", "a160", "a161", "a163", "a164",
+    "a196", "a165", "a192", "a166", "a167", "a168", "a169", "a170",
+    "a171", "a172", "a173", "a162", "a174", "a175", "a176", "a177",
+    "a178", "a179", "a193", "a180", "a199", "a181", "a200", "a182",
+    "", "a201", "a183", "a184", "a197", "a185", "a194", "a198",
+    "a186", "a195", "a187", "a188", "a189", "a190", "a191", "",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Standard14;
+
+    #[test]
+    fn encoding_vectors() {
+        let counts: Vec<_> = Encoding::ALL
+            .iter()
+            .map(|encoding| encoding.glyph_names().count())
+            .collect();
+        assert_eq!(counts, vec![149, 218, 208, 165, 229, 189, 202]);
+        for &encoding in &Encoding::ALL {
+            assert_eq!(Encoding::from_name(&encoding.to_string()), Some(encoding));
+            for (code, name) in encoding.glyph_names() {
+                assert_eq!(encoding.glyph_name(code), Some(name));
+                assert!(encoding.code(name).unwrap() <= code);
+            }
+        }
+        assert_eq!(
+            Encoding::from_name("AdobeStandardEncoding"),
+            Some(Encoding::Standard)
+        );
+        assert_eq!(Encoding::from_name("FontSpecific"), None);
+
+        assert_eq!(Encoding::Standard.glyph_name(0x27), Some("quoteright"));
+        assert_eq!(Encoding::WinAnsi.glyph_name(0x27), Some("quotesingle"));
+        assert_eq!(Encoding::WinAnsi.glyph_name(0x81), None);
+        assert_eq!(Encoding::WinAnsi.code("space"), Some(0x20));
+        assert_eq!(Encoding::WinAnsi.code("Euro"), Some(0x80));
+        assert_eq!(Encoding::MacRoman.glyph_name(0xDB), Some("currency"));
+        assert_eq!(Encoding::MacRoman.code("notequal"), None);
+        assert_eq!(Encoding::MacExpert.glyph_name(0x61), Some("Asmall"));
+        assert_eq!(Encoding::PdfDoc.glyph_name(0x18), Some("breve"));
+        assert_eq!(Encoding::PdfDoc.glyph_name(0xA0), Some("Euro"));
+        assert_eq!(Encoding::Symbol.glyph_name(0x61), Some("alpha"));
+        assert_eq!(Encoding::ZapfDingbats.glyph_name(0x21), Some("a1"));
+        assert_eq!(Encoding::Standard.code(""), None);
+    }
+
+    #[test]
+    fn view_through_encodings() {
+        for &(font, encoding) in &[
+            (Standard14::TimesRoman, Encoding::Standard),
+            (Standard14::Symbol, Encoding::Symbol),
+            (Standard14::ZapfDingbats, Encoding::ZapfDingbats),
+        ] {
+            let metrics = font.metrics();
+            let encoded = metrics.encoded(encoding);
+            let glyphs: Vec<_> = encoded.glyphs().collect();
+            let built_in: Vec<_> = metrics
+                .encoded_glyphs()
+                .map(|metric| (metric.character_code as u8, metric))
+                .collect();
+            assert_eq!(glyphs, built_in);
+        }
+
+        let metrics = Standard14::Helvetica.metrics();
+        let encoded = metrics.encoded(Encoding::WinAnsi);
+        assert_eq!(encoded.encoding(), Encoding::WinAnsi);
+        assert!(std::ptr::eq(encoded.metrics(), metrics));
+        assert_eq!(encoded.glyph(0x27).unwrap().wx, 191.0);
+        assert_eq!(encoded.glyph_id(0x41), metrics.glyph_id("A"));
+        assert_eq!(encoded.glyph(0x80).unwrap().name, "Euro");
+        assert_eq!(encoded.glyph(0x81), None);
+        assert_eq!(encoded.glyphs().count(), 218);
+        let encoded = metrics.encoded(Encoding::MacExpert);
+        assert_eq!(encoded.glyph(0x61), None);
+        assert_eq!(encoded.glyph(0x2C).unwrap().name, "comma");
+    }
+}
@@ -0,0 +1,274 @@
+use std::error;
+use std::fmt;
+use std::ops::Range;
+
+/// A location in the parsed input.
+///
+/// `line` and `column` are 1-based and count bytes, `offset` is the 0-based
+/// byte offset from the start of the input.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The line a parsed item was read from, such as a `CharMetric` or a
+/// header key.
+///
+/// `line` is 1-based, `start` and `end` are the byte offsets of the line
+/// in the input, without its line break.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Span {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The bytes of the line in the input.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}", self.line)
+    }
+}
+
+/// The section of an AFM file a problem was found in.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Section {
+    FontMetrics,
+    CharMetrics,
+    KernData,
+    KernPairs,
+    TrackKern,
+    Composites,
+    Direction,
+    MasterFontMetrics,
+    Axis,
+    Master,
+    PrimaryFonts,
+    ConversionPrograms,
+    CompFontMetrics,
+    Descendent,
+}
+
+impl Section {
+    /// The keyword opening this section.
+    pub fn start_keyword(&self) -> &'static str {
+        match *self {
+            Section::FontMetrics => "StartFontMetrics",
+            Section::CharMetrics => "StartCharMetrics",
+            Section::KernData => "StartKernData",
+            Section::KernPairs => "StartKernPairs",
+            Section::TrackKern => "StartTrackKern",
+            Section::Composites => "StartComposites",
+            Section::Direction => "StartDirection",
+            Section::MasterFontMetrics => "StartMasterFontMetrics",
+            Section::Axis => "StartAxis",
+            Section::Master => "StartMaster",
+            Section::PrimaryFonts => "StartPrimaryFonts",
+            Section::ConversionPrograms => "StartConversionPrograms",
+            Section::CompFontMetrics => "StartCompFontMetrics",
+            Section::Descendent => "StartDescendent",
+        }
+    }
+
+    /// The keyword closing this section.
+    pub fn end_keyword(&self) -> &'static str {
+        match *self {
+            Section::FontMetrics => "EndFontMetrics",
+            Section::CharMetrics => "EndCharMetrics",
+            Section::KernData => "EndKernData",
+            Section::KernPairs => "EndKernPairs",
+            Section::TrackKern => "EndTrackKern",
+            Section::Composites => "EndComposites",
+            Section::Direction => "EndDirection",
+            Section::MasterFontMetrics => "EndMasterFontMetrics",
+            Section::Axis => "EndAxis",
+            Section::Master => "EndMaster",
+            Section::PrimaryFonts => "EndPrimaryFonts",
+            Section::ConversionPrograms => "EndConversionPrograms",
+            Section::CompFontMetrics => "EndCompFontMetrics",
+            Section::Descendent => "EndDescendent",
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let start = self.start_keyword();
+        f.write_str(&start["Start".len()..])
+    }
+}
+
+/// A bound of `ParseLimits`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Limit {
+    Bytes,
+    LineLength,
+    Glyphs,
+    KernPairs,
+    CompositeParts,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Limit::Bytes => "bytes of input",
+            Limit::LineLength => "bytes in a line",
+            Limit::Glyphs => "char metrics",
+            Limit::KernPairs => "kern pairs",
+            Limit::CompositeParts => "parts in a composite",
+        })
+    }
+}
+
+/// An error encountered while parsing an AFM file.
+#[derive(PartialEq, Debug)]
+pub enum AfmError {
+    /// The input does not begin with the keyword opening `section`.
+    MissingStart {
+        section: Section,
+        position: Position,
+    },
+    /// The input ended before the keyword closing `section`.
+    MissingEnd {
+        section: Section,
+        position: Position,
+    },
+    /// A keyword that is not allowed in `section`.
+    UnexpectedKeyword {
+        keyword: String,
+        section: Section,
+        position: Position,
+    },
+    /// A known keyword followed by a malformed value, e.g. a bad number.
+    InvalidValue {
+        keyword: String,
+        section: Section,
+        position: Position,
+    },
+    /// The number of entries in `section` differs from the declared count.
+    CountMismatch {
+        section: Section,
+        declared: u32,
+        found: u32,
+        position: Position,
+    },
+    /// A kern pair, ligature or composite names a glyph without char
+    /// metrics. Only ever a warning, also in strict mode, as the name is
+    /// kept in `FontMetrics::unknown_glyphs`.
+    UnknownGlyph {
+        name: String,
+        section: Section,
+        position: Position,
+    },
+    /// The input exceeds one of the `ParseLimits`. This ends parsing also
+    /// in lenient mode.
+    LimitExceeded {
+        limit: Limit,
+        max: usize,
+        section: Section,
+        position: Position,
+    },
+}
+
+/// A problem that was skipped while parsing in lenient mode.
+pub type Warning = AfmError;
+
+impl AfmError {
+    /// Where in the input the error was detected.
+    pub fn position(&self) -> Position {
+        match *self {
+            AfmError::MissingStart { position, .. }
+            | AfmError::MissingEnd { position, .. }
+            | AfmError::UnexpectedKeyword { position, .. }
+            | AfmError::InvalidValue { position, .. }
+            | AfmError::CountMismatch { position, .. }
+            | AfmError::UnknownGlyph { position, .. }
+            | AfmError::LimitExceeded { position, .. } => position,
+        }
+    }
+
+    /// The section the error was detected in.
+    pub fn section(&self) -> Section {
+        match *self {
+            AfmError::MissingStart { section, .. }
+            | AfmError::MissingEnd { section, .. }
+            | AfmError::UnexpectedKeyword { section, .. }
+            | AfmError::InvalidValue { section, .. }
+            | AfmError::CountMismatch { section, .. }
+            | AfmError::UnknownGlyph { section, .. }
+            | AfmError::LimitExceeded { section, .. } => section,
+        }
+    }
+}
+
+impl fmt::Display for AfmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AfmError::MissingStart { section, position } => {
+                write!(f, "{}: expected {}", position, section.start_keyword())
+            }
+            AfmError::MissingEnd { section, position } => {
+                write!(f, "{}: missing {}", position, section.end_keyword())
+            }
+            AfmError::UnexpectedKeyword {
+                ref keyword,
+                section,
+                position,
+            } => write!(
+                f,
+                "{}: unexpected keyword `{}` in {}",
+                position, keyword, section
+            ),
+            AfmError::InvalidValue {
+                ref keyword,
+                section,
+                position,
+            } => write!(
+                f,
+                "{}: invalid value for `{}` in {}",
+                position, keyword, section
+            ),
+            AfmError::CountMismatch {
+                section,
+                declared,
+                found,
+                position,
+            } => write!(
+                f,
+                "{}: expected {} entries in {} but found {}",
+                position, declared, section, found
+            ),
+            AfmError::UnknownGlyph {
+                ref name,
+                section,
+                position,
+            } => write!(f, "{}: unknown glyph `{}` in {}", position, name, section),
+            AfmError::LimitExceeded {
+                limit,
+                max,
+                section,
+                position,
+            } => write!(
+                f,
+                "{}: more than {} {} in {}",
+                position, max, limit, section
+            ),
+        }
+    }
+}
+
+impl error::Error for AfmError {}
@@ -0,0 +1,303 @@
+use std::str;
+use std::sync::OnceLock;
+
+use super::{CharMetric, FontMetrics};
+
+// The glyph lists Adobe publishes at
+// https://github.com/adobe-type-tools/agl-aglfn, embedded as they are.
+
+static GLYPH_LIST: &str = include_str!("agl/glyphlist.txt");
+static NEW_FONT_LIST: &str = include_str!("agl/aglfn.txt");
+static ZAPF_DINGBATS_LIST: &str = include_str!("agl/zapfdingbats.txt");
+
+/// A glyph list, read from its file on first use.
+struct GlyphList {
+    /// The names and the text of each, sorted by name.
+    by_name: Vec<(&'static str, String)>,
+    /// The names standing for a single character, sorted by character and
+    /// then by name.
+    by_char: Vec<(char, &'static str)>,
+}
+
+impl GlyphList {
+    /// Reads the lines of `file` but comments, which hold the name and
+    /// the code points separated by spaces in the fields `name` and `code`.
+    fn read(file: &'static str, name: usize, code: usize) -> GlyphList {
+        let mut by_name: Vec<_> = file
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let fields: Vec<&str> = line.split(';').collect();
+                let text: Option<String> = fields[code]
+                    .split(' ')
+                    .map(|digits| upper_hex(digits).and_then(char::from_u32))
+                    .collect();
+                (fields[name], text.expect("code points of a glyph list"))
+            })
+            .collect();
+        by_name.sort_by(|a, b| a.0.cmp(b.0));
+        let mut by_char: Vec<_> = by_name
+            .iter()
+            .filter_map(|&(name, ref text)| {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some((c, name)),
+                    _ => None,
+                }
+            })
+            .collect();
+        by_char.sort_by_key(|&(c, _)| c);
+        GlyphList { by_name, by_char }
+    }
+
+    /// The text of `name`.
+    fn text(&self, name: &str) -> Option<&str> {
+        let i = self.by_name.binary_search_by(|&(entry, _)| entry.cmp(name));
+        i.ok().map(|i| &self.by_name[i].1[..])
+    }
+
+    /// The names of `c`, sorted.
+    fn names(&self, c: char) -> impl Iterator<Item = &'static str> + '_ {
+        let i = self.by_char.partition_point(|&(entry, _)| entry < c);
+        let names = self.by_char[i..]
+            .iter()
+            .take_while(move |&&(entry, _)| entry == c);
+        names.map(|&(_, name)| name)
+    }
+}
+
+/// The Adobe Glyph List.
+fn glyph_list() -> &'static GlyphList {
+    static LIST: OnceLock<GlyphList> = OnceLock::new();
+    LIST.get_or_init(|| GlyphList::read(GLYPH_LIST, 0, 1))
+}
+
+/// The Adobe Glyph List For New Fonts, which names each of its characters
+/// once.
+fn new_font_list() -> &'static GlyphList {
+    static LIST: OnceLock<GlyphList> = OnceLock::new();
+    LIST.get_or_init(|| GlyphList::read(NEW_FONT_LIST, 1, 0))
+}
+
+/// The ITC Zapf Dingbats Glyph List, for the names in the font
+/// `ZapfDingbats`. None of them is in the Adobe Glyph List.
+fn zapf_dingbats_list() -> &'static GlyphList {
+    static LIST: OnceLock<GlyphList> = OnceLock::new();
+    LIST.get_or_init(|| GlyphList::read(ZAPF_DINGBATS_LIST, 0, 1))
+}
+
+/// The value of `digits`, which must all be upper case hexadecimal digits.
+fn upper_hex(digits: &str) -> Option<u32> {
+    let digit = |c: u8| c.is_ascii_digit() || (b'A'..=b'F').contains(&c);
+    if digits.is_empty() || !digits.bytes().all(digit) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Appends the characters of one component of a glyph name to `text`,
+/// none if it has no meaning.
+fn push_component(component: &str, text: &mut String) {
+    let list_text = glyph_list().text(component);
+    if let Some(list_text) = list_text.or_else(|| zapf_dingbats_list().text(component)) {
+        text.push_str(list_text);
+        return;
+    }
+    if let Some(digits) = component.strip_prefix("uni") {
+        if digits.len() % 4 == 0 {
+            let chars: Option<String> = digits
+                .as_bytes()
+                .chunks(4)
+                .map(|digits| upper_hex(str::from_utf8(digits).ok()?).and_then(char::from_u32))
+                .collect();
+            if let Some(chars) = chars {
+                text.push_str(&chars);
+                return;
+            }
+        }
+    }
+    if let Some(digits) = component.strip_prefix('u') {
+        if (4..=6).contains(&digits.len()) {
+            if let Some(c) = upper_hex(digits).and_then(char::from_u32) {
+                text.push(c);
+            }
+        }
+    }
+}
+
+/// The text the glyph `name` stands for, by the Adobe Glyph List
+/// Specification:
+///
+/// - A suffix after a period, as in `a.sc`, is left out.
+/// - Components joined by underscores, as in `f_f_i`, are ligatures of
+///   the characters of each.
+/// - A component is a name of the Adobe Glyph List, `uni` followed by one
+///   or more groups of four upper case hexadecimal digits, or `u` followed
+///   by four to six such digits. Other components and digits naming
+///   surrogates have no characters.
+///
+/// Names of the ITC Zapf Dingbats Glyph List, such as `a1`, which the
+/// specification maps in the font `ZapfDingbats` only, are mapped too, as
+/// no other list has them.
+///
+/// `None` if the name stands for no characters, as does `.notdef`.
+///
+/// ```
+/// # use afm::glyph_name_to_unicode;
+/// let text = glyph_name_to_unicode("Lcommaaccent_uni20AC0308_u1040C.alternate");
+/// assert_eq!(text.unwrap(), "\u{013B}\u{20AC}\u{0308}\u{1040C}");
+/// assert_eq!(glyph_name_to_unicode("a1").unwrap(), "\u{2701}");
+/// ```
+pub fn glyph_name_to_unicode(name: &str) -> Option<String> {
+    let name = name.split('.').next().unwrap_or_default();
+    let mut text = String::new();
+    for component in name.split('_') {
+        push_component(component, &mut text);
+    }
+    Some(text).filter(|text| !text.is_empty())
+}
+
+/// The names a glyph of `c` may have, from the most to the least
+/// recommended: the name the AGL for New Fonts gives it, `uniXXXX`, other
+/// names of the Adobe Glyph List and `uXXXX`, such as `afii10017` after
+/// `uni0410`. `glyph_name_to_unicode` maps each back to `c`.
+///
+/// ```
+/// # use afm::unicode_to_glyph_names;
+/// assert_eq!(unicode_to_glyph_names('é'), ["eacute", "uni00E9", "u00E9"]);
+/// ```
+pub fn unicode_to_glyph_names(c: char) -> Vec<String> {
+    let new = new_font_list().names(c).next();
+    let mut names: Vec<String> = new.into_iter().map(String::from).collect();
+    if c <= '\u{FFFF}' {
+        names.push(format!("uni{:04X}", c as u32));
+    }
+    let old = glyph_list().names(c).filter(|&name| Some(name) != new);
+    names.extend(old.map(String::from));
+    names.push(format!("u{:04X}", c as u32));
+    names
+}
+
+impl FontMetrics {
+    /// The char metrics of the glyph for `c`, which has one of the names
+    /// of `unicode_to_glyph_names`, the most recommended found. In the font
+    /// `ZapfDingbats`, the names of the ITC Zapf Dingbats Glyph List come
+    /// first.
+    ///
+    /// ```
+    /// # use afm::Standard14;
+    /// let dingbats = Standard14::ZapfDingbats.metrics();
+    /// assert_eq!(dingbats.glyph_for_char('\u{2701}').unwrap().name, "a1");
+    /// ```
+    pub fn glyph_for_char(&self, c: char) -> Option<&CharMetric> {
+        if self.font_name == "ZapfDingbats" {
+            let mut names = zapf_dingbats_list().names(c);
+            if let Some(metric) = names.find_map(|name| self.glyph_by_name(name)) {
+                return Some(metric);
+            }
+        }
+        let names = unicode_to_glyph_names(c);
+        names.iter().find_map(|name| self.glyph_by_name(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Standard14;
+
+    #[test]
+    fn map_glyph_names() {
+        let text = |name| glyph_name_to_unicode(name);
+        assert_eq!(text("Lcommaaccent").unwrap(), "\u{013B}");
+        assert_eq!(text("uni20AC0308").unwrap(), "\u{20AC}\u{0308}");
+        assert_eq!(text("u1040C").unwrap(), "\u{1040C}");
+        assert_eq!(text("uni013B").unwrap(), "\u{013B}");
+        assert_eq!(text("u013B").unwrap(), "\u{013B}");
+        assert_eq!(text("f_f_i").unwrap(), "ffi");
+        assert_eq!(text("one.oldstyle").unwrap(), "1");
+        assert_eq!(text("a.sc").unwrap(), "a");
+        assert_eq!(text("Delta").unwrap(), "\u{2206}");
+        assert_eq!(text("afii10017").unwrap(), "\u{0410}");
+        assert_eq!(text("Asmall").unwrap(), "\u{F761}");
+        assert_eq!(text("foo_a").unwrap(), "a");
+        assert_eq!(text("dalethatafpatah").unwrap(), "\u{05D3}\u{05B2}");
+        assert_eq!(text("a1").unwrap(), "\u{2701}");
+        assert_eq!(text("a1_a2").unwrap(), "\u{2701}\u{2702}");
+        for name in &[
+            ".notdef",
+            "",
+            "foo",
+            "uniD801DC0C",
+            "uni20ac",
+            "uni20A",
+            "u110000",
+            "u12",
+            "u1234567",
+            "uniÄÄÄÄ",
+        ] {
+            assert_eq!(text(name), None, "{}", name);
+        }
+
+        assert_eq!(unicode_to_glyph_names('A'), ["A", "uni0041", "u0041"]);
+        assert_eq!(
+            unicode_to_glyph_names('\u{0394}'),
+            ["uni0394", "Deltagreek", "u0394"]
+        );
+        assert_eq!(
+            unicode_to_glyph_names('\u{2206}'),
+            ["Delta", "uni2206", "increment", "u2206"]
+        );
+        assert_eq!(
+            unicode_to_glyph_names('\u{0410}'),
+            ["uni0410", "Acyrillic", "afii10017", "u0410"]
+        );
+        assert_eq!(
+            unicode_to_glyph_names('\u{F761}'),
+            ["uniF761", "Asmall", "uF761"]
+        );
+        assert_eq!(unicode_to_glyph_names('\u{1F600}'), ["u1F600"]);
+        assert_eq!(glyph_list().by_name.len(), 4281);
+        assert_eq!(new_font_list().by_name.len(), 586);
+        assert_eq!(zapf_dingbats_list().by_name.len(), 201);
+        for &(name, ref text) in &glyph_list().by_name {
+            assert_eq!(glyph_name_to_unicode(name).as_ref(), Some(text));
+            let mut chars = text.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                assert!(unicode_to_glyph_names(c).iter().any(|entry| entry == name));
+                for name in unicode_to_glyph_names(c) {
+                    assert_eq!(glyph_name_to_unicode(&name).unwrap(), text.as_str());
+                }
+            }
+        }
+        for &(c, name) in &new_font_list().by_char {
+            assert_eq!(unicode_to_glyph_names(c)[0], name);
+        }
+    }
+
+    #[test]
+    fn find_glyphs_for_chars() {
+        let times = Standard14::TimesRoman.metrics();
+        assert_eq!(times.glyph_for_char('A').unwrap().name, "A");
+        assert_eq!(times.glyph_for_char('\u{FB01}').unwrap().name, "fi");
+        assert_eq!(times.glyph_for_char('\u{20AC}').unwrap().name, "Euro");
+        assert_eq!(times.glyph_for_char('\u{0410}'), None);
+        let symbol = Standard14::Symbol.metrics();
+        assert_eq!(symbol.glyph_for_char('\u{03B1}').unwrap().name, "alpha");
+        assert_eq!(symbol.glyph_for_char('\u{2206}').unwrap().name, "Delta");
+        assert_eq!(symbol.glyph_for_char('\u{0394}'), None);
+        let dingbats = Standard14::ZapfDingbats.metrics();
+        assert_eq!(dingbats.glyph_for_char('\u{2701}').unwrap().name, "a1");
+        assert_eq!(dingbats.glyph_for_char('\u{2794}').unwrap().name, "a160");
+        assert_eq!(dingbats.glyph_for_char(' ').unwrap().name, "space");
+        assert_eq!(times.glyph_for_char('\u{2701}'), None);
+
+        let metrics = FontMetrics::parse(
+            b"StartFontMetrics 4.1\nStartCharMetrics 3\nC -1 ; WX 600 ; N u0410 ;\nC -1 ; WX 600 ; N uni0410 ;\nC -1 ; WX 600 ; N u1F600 ;\nEndCharMetrics\nEndFontMetrics\n",
+        )
+        .unwrap();
+        assert_eq!(metrics.glyph_for_char('\u{0410}').unwrap().name, "uni0410");
+        assert_eq!(metrics.glyph_for_char('\u{1F600}').unwrap().name, "u1F600");
+        assert_eq!(metrics.glyph_for_char('A'), None);
+    }
+}
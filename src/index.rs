@@ -0,0 +1,597 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::slice;
+use std::sync::OnceLock;
+
+use super::{CharMetric, FontMetrics, KernPair};
+
+/// Identifies a glyph by its index in `FontMetrics::char_metrics`, or for
+/// a glyph without char metrics by its index in
+/// `FontMetrics::unknown_glyphs`, see `GlyphId::unknown`.
+///
+/// Kern pairs, ligatures and composites refer to glyphs by id, resolved
+/// from the glyph names when parsing. If several glyphs have the same name,
+/// the name refers to the first.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Default)]
+pub struct GlyphId(pub u32);
+
+/// The bit set in the ids of unknown glyphs.
+const UNKNOWN: u32 = 1 << 31;
+
+impl GlyphId {
+    /// The id of `FontMetrics::unknown_glyphs[index]`.
+    pub fn unknown(index: usize) -> GlyphId {
+        GlyphId(UNKNOWN | index as u32)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// The index into `FontMetrics::unknown_glyphs` of an unknown glyph,
+    /// or `None` for a glyph with char metrics.
+    pub fn unknown_index(self) -> Option<usize> {
+        if self.0 & UNKNOWN != 0 {
+            Some((self.0 & !UNKNOWN) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// The char metrics of a font, in the order of the file, indexed by glyph
+/// name and code.
+///
+/// Reads go through `Deref` to a slice. Changes go through `push` or
+/// `edit`, which keep the index up to date, so lookups never scan. The
+/// position of a glyph is its `GlyphId`, so glyphs are only ever appended,
+/// or removed by `FontMetrics::remove_glyph`, which renumbers the
+/// references to the glyphs after it.
+///
+/// ```
+/// # use afm::{CharMetric, CharMetrics};
+/// let glyph = |name: &str, code| CharMetric {
+///     name: name.to_string(),
+///     character_code: code,
+///     ..CharMetric::default()
+/// };
+/// let mut metrics = CharMetrics::from(vec![glyph("A", 65)]);
+/// metrics.push(glyph("B", 66));
+/// metrics.edit()[0].name = "C".to_string();
+/// assert_eq!(metrics.len(), 2);
+/// assert_eq!(metrics.index_of("C"), Some(0));
+/// assert_eq!(metrics.index_of("A"), None);
+/// ```
+#[derive(Clone, Default)]
+pub struct CharMetrics {
+    metrics: Vec<CharMetric>,
+    index: GlyphIndex,
+}
+
+/// The lookups of `CharMetrics`, derived from its metrics.
+#[derive(Clone, Default)]
+struct GlyphIndex {
+    by_name: HashMap<String, usize>,
+    by_code: HashMap<i32, usize>,
+    /// The glyphs with a code, ordered by code.
+    encoded: Vec<usize>,
+}
+
+impl GlyphIndex {
+    fn new(char_metrics: &[CharMetric]) -> GlyphIndex {
+        let mut index = GlyphIndex {
+            by_name: HashMap::with_capacity(char_metrics.len()),
+            by_code: HashMap::with_capacity(char_metrics.len()),
+            encoded: Vec::with_capacity(char_metrics.len()),
+        };
+        for (i, metric) in char_metrics.iter().enumerate() {
+            index.add(i, metric);
+        }
+        index
+            .encoded
+            .sort_by_key(|&i| char_metrics[i].character_code);
+        index
+    }
+
+    /// Indexes the glyph `i`, unless an earlier one has the same name or
+    /// code. Leaves `encoded` to be sorted.
+    fn add(&mut self, i: usize, metric: &CharMetric) {
+        self.by_name.entry(metric.name.clone()).or_insert(i);
+        if metric.character_code >= 0 {
+            if let Entry::Vacant(entry) = self.by_code.entry(metric.character_code) {
+                entry.insert(i);
+                self.encoded.push(i);
+            }
+        }
+    }
+}
+
+impl CharMetrics {
+    /// The index of the first glyph named `name`.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.index.by_name.get(name).cloned()
+    }
+
+    /// The index of the first glyph with the character code `code`.
+    pub fn index_of_code(&self, code: i32) -> Option<usize> {
+        self.index.by_code.get(&code).cloned()
+    }
+
+    /// Appends `metric`, updating the index.
+    pub fn push(&mut self, metric: CharMetric) {
+        let i = self.metrics.len();
+        self.index.add(i, &metric);
+        if self.index.encoded.last() == Some(&i) {
+            // Move the new code into its place among the ordered ones.
+            self.index.encoded.pop();
+            let metrics = &self.metrics;
+            let code = metric.character_code;
+            let at = self
+                .index
+                .encoded
+                .partition_point(|&j| metrics[j].character_code <= code);
+            self.index.encoded.insert(at, i);
+        }
+        self.metrics.push(metric);
+    }
+
+    /// Mutable access to the metrics in place, which cannot reorder them.
+    /// The index is rebuilt when the returned guard is dropped, so make
+    /// related changes through one.
+    pub fn edit(&mut self) -> CharMetricsMut<'_> {
+        CharMetricsMut(self)
+    }
+
+    fn remove(&mut self, i: usize) -> CharMetric {
+        let metric = self.metrics.remove(i);
+        self.index = GlyphIndex::new(&self.metrics);
+        metric
+    }
+
+    /// The metrics without the index.
+    pub fn into_vec(self) -> Vec<CharMetric> {
+        self.metrics
+    }
+}
+
+impl Deref for CharMetrics {
+    type Target = [CharMetric];
+
+    fn deref(&self) -> &[CharMetric] {
+        &self.metrics
+    }
+}
+
+impl<'a> IntoIterator for &'a CharMetrics {
+    type Item = &'a CharMetric;
+    type IntoIter = slice::Iter<'a, CharMetric>;
+
+    fn into_iter(self) -> slice::Iter<'a, CharMetric> {
+        self.metrics.iter()
+    }
+}
+
+impl From<Vec<CharMetric>> for CharMetrics {
+    fn from(metrics: Vec<CharMetric>) -> CharMetrics {
+        CharMetrics {
+            index: GlyphIndex::new(&metrics),
+            metrics,
+        }
+    }
+}
+
+impl FromIterator<CharMetric> for CharMetrics {
+    fn from_iter<I: IntoIterator<Item = CharMetric>>(metrics: I) -> CharMetrics {
+        CharMetrics::from(metrics.into_iter().collect::<Vec<_>>())
+    }
+}
+
+/// The index is derived data and never makes two `CharMetrics` unequal.
+impl PartialEq for CharMetrics {
+    fn eq(&self, other: &CharMetrics) -> bool {
+        self.metrics == other.metrics
+    }
+}
+
+impl fmt::Debug for CharMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.metrics.fmt(f)
+    }
+}
+
+/// Mutable access to `CharMetrics`, see `CharMetrics::edit`.
+pub struct CharMetricsMut<'a>(&'a mut CharMetrics);
+
+impl<'a> CharMetricsMut<'a> {
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut CharMetric> {
+        self.0.metrics.get_mut(i)
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, CharMetric> {
+        self.0.metrics.iter_mut()
+    }
+}
+
+impl<'a> Deref for CharMetricsMut<'a> {
+    type Target = [CharMetric];
+
+    fn deref(&self) -> &[CharMetric] {
+        &self.0.metrics
+    }
+}
+
+impl<'a> Index<usize> for CharMetricsMut<'a> {
+    type Output = CharMetric;
+
+    fn index(&self, i: usize) -> &CharMetric {
+        &self.0.metrics[i]
+    }
+}
+
+impl<'a> IndexMut<usize> for CharMetricsMut<'a> {
+    fn index_mut(&mut self, i: usize) -> &mut CharMetric {
+        &mut self.0.metrics[i]
+    }
+}
+
+impl<'a> Drop for CharMetricsMut<'a> {
+    fn drop(&mut self) {
+        self.0.index = GlyphIndex::new(&self.0.metrics);
+    }
+}
+
+/// The kern pairs of one set, in the order of the file, with a lookup by
+/// glyphs, see `find`.
+///
+/// Like `CharMetrics`, reads go through `Deref` to a slice and changes
+/// through `push` or `edit`. The lookup is sorted when first used after a
+/// change, so parsing does not pay for it.
+#[derive(Clone, Default)]
+pub struct KernPairs {
+    pairs: Vec<KernPair>,
+    /// The indices of `pairs` ordered by their glyphs, and by position
+    /// among equal pairs.
+    sorted: OnceLock<Vec<usize>>,
+}
+
+impl KernPairs {
+    /// The first pair of `first` followed by `second`.
+    pub fn find(&self, first: GlyphId, second: GlyphId) -> Option<&KernPair> {
+        let pairs = &self.pairs;
+        let key = |i: usize| {
+            (
+                pairs[i].first_kern_character,
+                pairs[i].second_kern_character,
+            )
+        };
+        let sorted = self.sorted.get_or_init(|| {
+            let mut sorted: Vec<_> = (0..pairs.len()).collect();
+            sorted.sort_by_key(|&i| key(i));
+            sorted
+        });
+        let at = sorted.partition_point(|&i| key(i) < (first, second));
+        let i = *sorted.get(at).filter(|&&i| key(i) == (first, second))?;
+        Some(&pairs[i])
+    }
+
+    /// Appends `pair`.
+    pub fn push(&mut self, pair: KernPair) {
+        self.sorted.take();
+        self.pairs.push(pair);
+    }
+
+    /// Mutable access to the pairs, see `CharMetrics::edit`.
+    pub fn edit(&mut self) -> KernPairsMut<'_> {
+        self.sorted.take();
+        KernPairsMut(self)
+    }
+
+    /// The pairs without the lookup.
+    pub fn into_vec(self) -> Vec<KernPair> {
+        self.pairs
+    }
+}
+
+impl Deref for KernPairs {
+    type Target = [KernPair];
+
+    fn deref(&self) -> &[KernPair] {
+        &self.pairs
+    }
+}
+
+impl<'a> IntoIterator for &'a KernPairs {
+    type Item = &'a KernPair;
+    type IntoIter = slice::Iter<'a, KernPair>;
+
+    fn into_iter(self) -> slice::Iter<'a, KernPair> {
+        self.pairs.iter()
+    }
+}
+
+impl From<Vec<KernPair>> for KernPairs {
+    fn from(pairs: Vec<KernPair>) -> KernPairs {
+        KernPairs {
+            pairs,
+            sorted: OnceLock::new(),
+        }
+    }
+}
+
+impl FromIterator<KernPair> for KernPairs {
+    fn from_iter<I: IntoIterator<Item = KernPair>>(pairs: I) -> KernPairs {
+        KernPairs::from(pairs.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl PartialEq for KernPairs {
+    fn eq(&self, other: &KernPairs) -> bool {
+        self.pairs == other.pairs
+    }
+}
+
+impl fmt::Debug for KernPairs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.pairs.fmt(f)
+    }
+}
+
+/// Mutable access to `KernPairs`, see `KernPairs::edit`.
+pub struct KernPairsMut<'a>(&'a mut KernPairs);
+
+impl<'a> Deref for KernPairsMut<'a> {
+    type Target = Vec<KernPair>;
+
+    fn deref(&self) -> &Vec<KernPair> {
+        &self.0.pairs
+    }
+}
+
+impl<'a> DerefMut for KernPairsMut<'a> {
+    fn deref_mut(&mut self) -> &mut Vec<KernPair> {
+        &mut self.0.pairs
+    }
+}
+
+impl FontMetrics {
+    /// Removes the glyph `id` and renumbers the references of kern pairs,
+    /// ligatures and composites to the glyphs after it. References to the
+    /// removed glyph are resolved again by its name, to the next glyph of
+    /// that name or else to an unknown glyph, which `validate` reports.
+    pub fn remove_glyph(&mut self, id: GlyphId) -> Option<CharMetric> {
+        self.glyph(id)?;
+        let removed = self.char_metrics.remove(id.index());
+        let replacement = self.resolve_glyph(&removed.name).unwrap_or_else(|| {
+            self.unknown_glyphs.push(removed.name.clone());
+            GlyphId::unknown(self.unknown_glyphs.len() - 1)
+        });
+        let renumber = |glyph: &mut GlyphId| {
+            if *glyph == id {
+                *glyph = replacement;
+            } else if glyph.unknown_index().is_none() && *glyph > id {
+                glyph.0 -= 1;
+            }
+        };
+        for pairs in &mut [
+            &mut self.kern_pairs,
+            &mut self.kern_pairs0,
+            &mut self.kern_pairs1,
+        ] {
+            for pair in pairs.edit().iter_mut() {
+                renumber(&mut pair.first_kern_character);
+                renumber(&mut pair.second_kern_character);
+            }
+        }
+        for metric in self.char_metrics.edit().iter_mut() {
+            for ligature in &mut metric.ligatures {
+                renumber(&mut ligature.successor);
+                renumber(&mut ligature.ligature);
+            }
+        }
+        for composite in &mut self.composites {
+            for part in &mut composite.parts {
+                renumber(&mut part.glyph);
+            }
+        }
+        Some(removed)
+    }
+
+    /// The id of the glyph named `name`.
+    pub fn glyph_id(&self, name: &str) -> Option<GlyphId> {
+        let i = self.char_metrics.index_of(name)?;
+        Some(GlyphId(i as u32))
+    }
+
+    /// The id of the glyph named `name`, with or without char metrics.
+    pub(crate) fn resolve_glyph(&self, name: &str) -> Option<GlyphId> {
+        self.glyph_id(name).or_else(|| {
+            let unknown = self
+                .unknown_glyphs
+                .iter()
+                .position(|unknown| unknown == name);
+            unknown.map(GlyphId::unknown)
+        })
+    }
+
+    /// The char metrics of the glyph `id`, `None` for unknown glyphs.
+    pub fn glyph(&self, id: GlyphId) -> Option<&CharMetric> {
+        if id.unknown_index().is_some() {
+            return None;
+        }
+        self.char_metrics.get(id.index())
+    }
+
+    /// The name of the glyph `id`, also of unknown glyphs.
+    pub fn glyph_name(&self, id: GlyphId) -> Option<&str> {
+        match id.unknown_index() {
+            Some(i) => self.unknown_glyphs.get(i).map(|name| &name[..]),
+            None => self.glyph(id).map(|metric| &metric.name[..]),
+        }
+    }
+
+    /// The char metrics of the glyph named `name`.
+    pub fn glyph_by_name(&self, name: &str) -> Option<&CharMetric> {
+        self.glyph_id(name).and_then(|id| self.glyph(id))
+    }
+
+    /// The id of the glyph with the character code `code`.
+    pub fn glyph_id_by_code(&self, code: i32) -> Option<GlyphId> {
+        let i = self.char_metrics.index_of_code(code)?;
+        Some(GlyphId(i as u32))
+    }
+
+    /// The char metrics of the glyph with the character code `code`.
+    pub fn glyph_by_code(&self, code: i32) -> Option<&CharMetric> {
+        self.glyph_id_by_code(code).and_then(|id| self.glyph(id))
+    }
+
+    /// The glyphs with a character code, ordered by code. Glyphs that are
+    /// not encoded have the code -1.
+    pub fn encoded_glyphs<'a>(&'a self) -> impl Iterator<Item = &'a CharMetric> + 'a {
+        let char_metrics = &self.char_metrics;
+        let encoded = char_metrics.index.encoded.iter();
+        encoded.map(move |&i| &char_metrics[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn look_up_glyphs() {
+        let input = b"StartFontMetrics 4.1
+StartCharMetrics 4
+C 66 ; WX 600 ; N B ;
+C 65 ; WX 500 ; N A ;
+C -1 ; WX 300 ; N Aring ;
+C 65 ; WX 400 ; N A.alt ;
+EndCharMetrics
+EndFontMetrics
+";
+        let mut metrics = FontMetrics::parse(input).unwrap();
+        assert_eq!(metrics.glyph_by_name("A").unwrap().wx, 500.0);
+        assert_eq!(metrics.glyph_by_code(65).unwrap().name, "A");
+        assert_eq!(metrics.glyph_by_code(-1), None);
+        assert_eq!(metrics.glyph_by_name("C"), None);
+        assert_eq!(metrics.glyph_id("A.alt"), Some(GlyphId(3)));
+        assert_eq!(metrics.glyph_name(GlyphId(0)), Some("B"));
+        assert_eq!(metrics.glyph(GlyphId(4)), None);
+        metrics.unknown_glyphs.push("C".to_string());
+        assert_eq!(metrics.glyph_name(GlyphId::unknown(0)), Some("C"));
+        assert_eq!(metrics.glyph(GlyphId::unknown(0)), None);
+        assert_eq!(GlyphId::unknown(0).unknown_index(), Some(0));
+        assert_eq!(GlyphId(0).unknown_index(), None);
+        let names: Vec<_> = metrics.encoded_glyphs().map(|m| &m.name[..]).collect();
+        assert_eq!(names, vec!["A", "B"]);
+
+        metrics.remove_glyph(GlyphId(0)).unwrap();
+        assert_eq!(metrics.glyph_by_name("Aring").unwrap().wx, 300.0);
+        assert_eq!(metrics.glyph_by_code(65).unwrap().name, "A");
+        assert_eq!(metrics.glyph_by_name("B"), None);
+        assert_eq!(metrics.glyph_by_code(66), None);
+
+        // Renaming or recoding in place updates the lookups.
+        {
+            let mut char_metrics = metrics.char_metrics.edit();
+            char_metrics[0].name = "Z".to_string();
+            char_metrics[1].character_code = 66;
+        }
+        assert_eq!(metrics.glyph_by_name("A"), None);
+        assert_eq!(metrics.glyph_id("Z"), Some(GlyphId(0)));
+        assert_eq!(metrics.glyph_by_code(66).unwrap().name, "Aring");
+
+        metrics.char_metrics.push(CharMetric {
+            name: "C".to_string(),
+            character_code: 10,
+            ..CharMetric::default()
+        });
+        assert_eq!(metrics.glyph_id("C"), Some(GlyphId(3)));
+        let names: Vec<_> = metrics.encoded_glyphs().map(|m| &m.name[..]).collect();
+        assert_eq!(names, vec!["C", "Z", "Aring"]);
+    }
+
+    #[test]
+    fn remove_glyphs() {
+        let input = b"StartFontMetrics 4.1
+StartCharMetrics 4
+C 65 ; WX 600 ; N A ; L V W ;
+C 86 ; WX 600 ; N V ;
+C 84 ; WX 600 ; N T ;
+C 87 ; WX 600 ; N W ;
+EndCharMetrics
+StartKernData
+StartKernPairs 3
+KPX A V -80
+KPX T A -40
+KPX W T -10
+EndKernPairs
+EndKernData
+StartComposites 1
+CC Wcomp 2 ; PCC W 0 0 ; PCC V 0 0 ;
+EndComposites
+EndFontMetrics
+";
+        let mut metrics = FontMetrics::parse(input).unwrap();
+        let removed = metrics.remove_glyph(GlyphId(1)).unwrap();
+        assert_eq!(removed.name, "V");
+        assert_eq!(metrics.remove_glyph(GlyphId(4)), None);
+
+        let name = |id| metrics.glyph_name(id).unwrap();
+        let pairs: Vec<_> = metrics
+            .kern_pairs
+            .iter()
+            .map(|pair| {
+                (
+                    name(pair.first_kern_character),
+                    name(pair.second_kern_character),
+                )
+            })
+            .collect();
+        assert_eq!(pairs, vec![("A", "V"), ("T", "A"), ("W", "T")]);
+        let (t, a) = (
+            metrics.glyph_id("T").unwrap(),
+            metrics.glyph_id("A").unwrap(),
+        );
+        assert_eq!(metrics.kern_pairs.find(t, a).unwrap().x, -40.0);
+        assert_eq!(metrics.unknown_glyphs, vec!["V"]);
+        let ligature = &metrics.char_metrics[0].ligatures[0];
+        assert_eq!(
+            (name(ligature.successor), name(ligature.ligature)),
+            ("V", "W")
+        );
+        let parts = &metrics.composites[0].parts;
+        assert_eq!((name(parts[0].glyph), name(parts[1].glyph)), ("W", "V"));
+
+        let codes: Vec<_> = metrics.validate().iter().map(|d| d.code()).collect();
+        assert_eq!(
+            codes,
+            vec!["AFM009", "AFM009", "AFM002", "AFM003", "AFM004"]
+        );
+    }
+
+    #[test]
+    fn find_kern_pairs() {
+        let pair = |first, second, x| KernPair {
+            first_kern_character: GlyphId(first),
+            second_kern_character: GlyphId(second),
+            x,
+            y: 0.0,
+            span: None,
+        };
+        let mut pairs = KernPairs::from(vec![pair(1, 0, -80.0), pair(0, 1, -40.0)]);
+        pairs.push(pair(1, 0, -10.0));
+        assert_eq!(pairs.find(GlyphId(1), GlyphId(0)).unwrap().x, -80.0);
+        assert_eq!(pairs.find(GlyphId(0), GlyphId(1)).unwrap().x, -40.0);
+        assert_eq!(pairs.find(GlyphId(0), GlyphId(0)), None);
+
+        pairs.edit().remove(0);
+        assert_eq!(pairs.find(GlyphId(1), GlyphId(0)).unwrap().x, -10.0);
+        pairs.edit()[1].second_kern_character = GlyphId(2);
+        assert_eq!(pairs.find(GlyphId(1), GlyphId(0)), None);
+        assert_eq!(pairs.find(GlyphId(1), GlyphId(2)).unwrap().x, -10.0);
+    }
+}
@@ -1,26 +1,87 @@
+#![allow(clippy::precedence)]
+
 use pom::char_class;
-use pom::Parser;
+use pom::{DataInput, Input, Parser};
 use pom::parser::*;
 use std::str::FromStr;
 use std::string::*;
 use std::str;
 use std::char;
 
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::mem;
+
+use borrowed::HandWritten;
+use build::Builder;
 
-extern crate geo;
 extern crate pom;
 
-use geo::Bbox;
+mod borrowed;
+mod build;
+mod codegen;
+mod comment;
+mod composite;
+mod document;
+mod encoding;
+mod error;
+mod glyph_list;
+mod index;
+mod master;
+mod read;
+mod rect;
+mod standard14;
+mod static_metrics;
+mod validate;
+mod visit;
+mod write;
+
+pub use borrowed::{
+    CharMetricRef, CompositePartRef, CompositeRef, FontMetricsRef, KernPairRef, LigatureRef,
+};
+pub use codegen::static_name;
+pub use comment::{CreationDate, VmUsage};
+pub use composite::{CompositeFontMetrics, Descendant};
+pub use document::{Document, DocumentLine};
+pub use encoding::{EncodedFontMetrics, Encoding};
+pub use error::{AfmError, Limit, Position, Section, Span, Warning};
+pub use glyph_list::{glyph_name_to_unicode, unicode_to_glyph_names};
+pub use index::{CharMetrics, CharMetricsMut, GlyphId, KernPairs, KernPairsMut};
+pub use master::{Axis, Master, MasterFontMetrics, PrimaryFont};
+pub use rect::Rect;
+pub use standard14::Standard14;
+pub use static_metrics::{Metrics, StaticCharMetric, StaticFontMetrics, StaticKernPair};
+pub use validate::{Diagnostic, Lint, Severity};
+pub use visit::{AfmVisitor, KernPairSet};
+
+/// Implements `PartialEq` comparing every field but the one holding where
+/// the value was read from, so that metrics compare equal wherever they
+/// come from. Lists all fields, so that adding one fails to compile until
+/// it is listed here too.
+macro_rules! eq_without_spans {
+    ($type:ident { $($field:ident),* $(,)* } ignoring $span:ident) => {
+        impl PartialEq for $type {
+            fn eq(&self, other: &$type) -> bool {
+                let $type { $($field: _,)* $span: _ } = *self;
+                $(self.$field == other.$field)&&*
+            }
+        }
+    };
+}
 
-#[derive(PartialEq, Debug)]
+#[derive(Debug, Clone)]
 pub struct FontMetrics {
+    /// The version of the AFM format following `StartFontMetrics`.
+    pub afm_version: AfmVersion,
     pub metric_sets: i32,
     pub font_name: String,
     pub full_name: String,
     pub family_name: String,
     pub weight: String,
-    pub font_bbox: Bbox<f64>,
+    pub font_bbox: Rect,
     pub font_version: String,
     pub notice: String,
     pub encoding_scheme: String,
@@ -37,38 +98,47 @@ pub struct FontMetrics {
     pub descender: f64,
     pub comments: Vec<String>,
 
-    pub underline_position: f64,
-    pub underline_thickness: f64,
-    pub italic_angle: f64,
-    pub char_width: (f64, f64),
-    pub is_fixed_pitch: bool,
+    /// Metrics for writing direction 0, present unless `metric_sets` is 1.
+    pub direction0: Option<DirectionMetrics>,
+    /// Metrics for writing direction 1, present if `metric_sets` is 1 or 2.
+    pub direction1: Option<DirectionMetrics>,
     pub standard_horizontal_width: f64,
     pub standard_vertical_width: f64,
 
-    pub char_metrics: Vec<CharMetric>,
-    pub char_metrics_map: HashMap<String, CharMetric>,
+    /// Indexed by name and code, see `glyph_by_name`.
+    pub char_metrics: CharMetrics,
     pub track_kern: Vec<TrackKern>,
     pub composites: Vec<Composite>,
-    pub kern_pairs: Vec<KernPair>,
-    pub kern_pairs0: Vec<KernPair>,
-    pub kern_pairs1: Vec<KernPair>,
+    pub kern_pairs: KernPairs,
+    pub kern_pairs0: KernPairs,
+    pub kern_pairs1: KernPairs,
+    /// The names that ligatures, kern pairs and composites give glyphs
+    /// without char metrics, in that order. They are referred to by
+    /// `GlyphId::unknown`, reported as `UnknownGlyph` warnings when parsing
+    /// and by `validate`.
+    pub unknown_glyphs: Vec<String>,
+
+    /// Keys the format does not define, such as foundry specific ones,
+    /// with their values in the order they appear. Lenient parsing also
+    /// keeps misplaced keys of the format here.
+    pub extra_keys: Vec<(String, String)>,
+    /// The keyword and line of each header key read, in the order they
+    /// appear, see `header_span`. Includes `Comment`s and the keys of
+    /// `StartDirection` blocks.
+    pub header_spans: Vec<(String, Span)>,
 }
 
 impl Default for FontMetrics {
     fn default() -> FontMetrics {
         FontMetrics {
             // TODO check for actual default values
+            afm_version: AfmVersion::default(),
             metric_sets: 0,
             font_name: String::new(),
             full_name: String::new(),
             family_name: String::new(),
             weight: String::new(),
-            font_bbox: Bbox {
-                xmin: 0.,
-                xmax: 0.,
-                ymin: 0.,
-                ymax: 0.,
-            },
+            font_bbox: Rect::default(),
             font_version: String::new(),
             notice: String::new(),
             encoding_scheme: String::new(),
@@ -85,29 +155,128 @@ impl Default for FontMetrics {
             descender: 0.0,
             comments: Vec::new(),
 
-            underline_position: 0.0,
-            underline_thickness: 0.0,
-            italic_angle: 0.0,
-            char_width: (0.0, 0.0),
-            is_fixed_pitch: true,
+            direction0: None,
+            direction1: None,
             standard_horizontal_width: 0.0,
             standard_vertical_width: 0.0,
 
-            char_metrics: Vec::new(),
-            char_metrics_map: HashMap::new(),
+            char_metrics: CharMetrics::default(),
             track_kern: Vec::new(),
             composites: Vec::new(),
-            kern_pairs: Vec::new(),
-            kern_pairs0: Vec::new(),
-            kern_pairs1: Vec::new(),
+            kern_pairs: KernPairs::default(),
+            kern_pairs0: KernPairs::default(),
+            kern_pairs1: KernPairs::default(),
+            unknown_glyphs: Vec::new(),
+
+            extra_keys: Vec::new(),
+            header_spans: Vec::new(),
         }
     }
 }
 
-#[derive(PartialEq, Debug)]
+eq_without_spans!(FontMetrics {
+    afm_version,
+    metric_sets,
+    font_name,
+    full_name,
+    family_name,
+    weight,
+    font_bbox,
+    font_version,
+    notice,
+    encoding_scheme,
+    mapping_scheme,
+    esc_char,
+    character_set,
+    characters,
+    is_base_font,
+    v_vector,
+    is_fixed_v,
+    cap_height,
+    x_height,
+    ascender,
+    descender,
+    comments,
+    direction0,
+    direction1,
+    standard_horizontal_width,
+    standard_vertical_width,
+    char_metrics,
+    track_kern,
+    composites,
+    kern_pairs,
+    kern_pairs0,
+    kern_pairs1,
+    unknown_glyphs,
+    extra_keys
+} ignoring header_spans);
+
+/// The version of the AFM format a file is written in, such as 4.1.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct AfmVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl AfmVersion {
+    pub fn new(major: u32, minor: u32) -> AfmVersion {
+        AfmVersion { major, minor }
+    }
+}
+
+/// The latest version, 4.1.
+impl Default for AfmVersion {
+    fn default() -> AfmVersion {
+        AfmVersion::new(4, 1)
+    }
+}
+
+impl fmt::Display for AfmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The metrics given per writing direction, either in a
+/// `StartDirection`/`EndDirection` block or, for direction 0, at the top level.
+#[derive(PartialEq, Debug, Clone)]
+pub struct DirectionMetrics {
+    pub underline_position: f64,
+    pub underline_thickness: f64,
+    pub italic_angle: f64,
+    pub char_width: (f64, f64),
+    /// False unless `IsFixedPitch true` is given.
+    pub is_fixed_pitch: bool,
+}
+
+impl Default for DirectionMetrics {
+    fn default() -> DirectionMetrics {
+        DirectionMetrics {
+            underline_position: 0.0,
+            underline_thickness: 0.0,
+            italic_angle: 0.0,
+            char_width: (0.0, 0.0),
+            is_fixed_pitch: false,
+        }
+    }
+}
+
+impl DirectionMetrics {
+    fn apply(&mut self, command: DirectionCmd) {
+        match command {
+            DirectionCmd::UnderlinePosition(pos) => self.underline_position = pos,
+            DirectionCmd::UnderlineThickness(thickness) => self.underline_thickness = thickness,
+            DirectionCmd::ItalicAngle(angle) => self.italic_angle = angle,
+            DirectionCmd::CharWidth(w1, w2) => self.char_width = (w1, w2),
+            DirectionCmd::IsFixedPitch(fixed) => self.is_fixed_pitch = fixed,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct CharMetric {
     pub name: String,
-    pub bbox: Bbox<f64>,
+    pub bbox: Rect,
     pub ligatures: Vec<Ligature>,
     pub character_code: i32,
     pub wx: f64,
@@ -120,18 +289,36 @@ pub struct CharMetric {
     pub w0: (f64, f64),
     pub w1: (f64, f64),
     pub vv: (f64, f64),
+    /// Unknown keys of the `C ... ;` line with their values, see
+    /// `FontMetrics::extra_keys`.
+    pub extra_keys: Vec<(String, String)>,
+    /// The line the metric was read from, `None` unless parsed.
+    pub span: Option<Span>,
 }
 
+eq_without_spans!(CharMetric {
+    name,
+    bbox,
+    ligatures,
+    character_code,
+    wx,
+    w0x,
+    w1x,
+    wy,
+    w0y,
+    w1y,
+    w,
+    w0,
+    w1,
+    vv,
+    extra_keys
+} ignoring span);
+
 impl Default for CharMetric {
     fn default() -> CharMetric {
         CharMetric {
             name: String::new(),
-            bbox: Bbox {
-                xmin: 0.,
-                xmax: 0.,
-                ymin: 0.,
-                ymax: 0.,
-            },
+            bbox: Rect::default(),
             ligatures: Vec::new(),
             character_code: 0,
             wx: 0.0,
@@ -144,11 +331,13 @@ impl Default for CharMetric {
             w0: (0.0, 0.0),
             w1: (0.0, 0.0),
             vv: (0.0, 0.0),
+            extra_keys: Vec::new(),
+            span: None,
         }
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct TrackKern {
     pub degree: i32,
     pub min_point_size: f64,
@@ -157,57 +346,74 @@ pub struct TrackKern {
     pub max_kern: f64,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Debug, Clone)]
 pub struct KernPair {
-    pub first_kern_character: String,
-    pub second_kern_character: String,
+    pub first_kern_character: GlyphId,
+    pub second_kern_character: GlyphId,
     pub x: f64,
     pub y: f64,
+    /// The line the pair was read from, `None` unless parsed.
+    pub span: Option<Span>,
 }
 
-#[derive(PartialEq, Debug)]
+eq_without_spans!(KernPair {
+    first_kern_character,
+    second_kern_character,
+    x,
+    y
+} ignoring span);
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct Ligature {
-    pub successor: String,
-    pub ligature: String,
+    pub successor: GlyphId,
+    pub ligature: GlyphId,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Debug, Clone)]
 pub struct Composite {
     pub name: String,
     pub parts: Vec<CompositePart>,
+    /// The line the composite was read from, `None` unless parsed.
+    pub span: Option<Span>,
 }
 
-#[derive(PartialEq, Debug)]
+eq_without_spans!(Composite {
+    name,
+    parts
+} ignoring span);
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct CompositePart {
-    pub name: String,
+    pub glyph: GlyphId,
     pub x_displacement: i32,
     pub y_displacement: i32,
 }
 
+/// A byte of a string value: printable ASCII, tabs and the bytes above
+/// ASCII, which `decode_text` reads as UTF-8 or Latin-1.
 fn string_char(c: u8) -> bool {
-    c >= 0x20 && c <= 0x7E
+    c == b'\t' || (0x20..=0x7E).contains(&c) || c >= 0x80
 }
 
+/// A byte of a name or keyword, which are printable ASCII.
 fn name_char(c: u8) -> bool {
-    string_char(c) && (!char_class::space(c))
+    (0x21..=0x7E).contains(&c)
 }
 
 fn digit(c: u8) -> bool {
-    c >= b'0' && c <= b'9'
+    c.is_ascii_digit()
 }
 
 fn space() -> Parser<u8, ()> {
     is_a(char_class::space).repeat(1..).discard()
 }
 
-fn eol() -> Parser<u8, ()> {
-    (is_a(char_class::space).repeat(0..) - one_of(b"\r\n").repeat(1..)).discard()
-}
-
 // Types
 
 fn string() -> Parser<u8, String> {
-    is_a(string_char).repeat(1..).convert(String::from_utf8)
+    is_a(string_char)
+        .repeat(1..)
+        .map(|bytes| decode_text(&bytes).into_owned())
 }
 
 fn name() -> Parser<u8, String> {
@@ -227,6 +433,18 @@ fn integer() -> Parser<u8, i32> {
         .convert(|s| i32::from_str(&s))
 }
 
+/// A version like `4.1`, where the minor version may be left out.
+fn version() -> Parser<u8, AfmVersion> {
+    let number = || {
+        is_a(digit)
+            .repeat(1..)
+            .convert(String::from_utf8)
+            .convert(|s| u32::from_str(&s))
+    };
+    let minor = (sym(b'.') * number()).opt().map(Option::unwrap_or_default);
+    (number() + minor).map(|(major, minor)| AfmVersion::new(major, minor))
+}
+
 fn uinteger() -> Parser<u8, u32> {
     let integer = one_of(b"123456789") - one_of(b"0123456789").repeat(0..) | sym(b'0');
     integer
@@ -238,6 +456,7 @@ fn uinteger() -> Parser<u8, u32> {
 fn hex_integer() -> Parser<u8, i32> {
     let hex_digits = is_a(char_class::hex_digit).repeat(1..).collect();
     sym(b'<') * hex_digits.convert(|v| i32::from_str_radix(&String::from_utf8(v).unwrap(), 16))
+        - sym(b'>')
 }
 
 fn number() -> Parser<u8, f64> {
@@ -251,14 +470,9 @@ fn number() -> Parser<u8, f64> {
         .convert(|s| f64::from_str(&s))
 }
 
-fn bbox() -> Parser<u8, Bbox<f64>> {
+fn bbox() -> Parser<u8, Rect> {
     let numbers = (number() - space()).repeat(3) + number();
-    numbers.map(|(nums, num)| Bbox {
-        xmin: nums[0],
-        xmax: nums[1],
-        ymin: nums[2],
-        ymax: num,
-    })
+    numbers.map(|(nums, num)| Rect::new(nums[0], nums[1], nums[2], num))
 }
 
 // Combinators
@@ -267,65 +481,66 @@ fn start_command(command: &'static [u8]) -> Parser<u8, ()> {
     (seq(command) * space()).discard()
 }
 
-fn string_command(
+fn string_command<C: 'static>(
     command: &'static [u8],
-    build: &'static Fn(String) -> Command,
-) -> Parser<u8, Command> {
+    build: &'static dyn Fn(String) -> C,
+) -> Parser<u8, C> {
     start_command(command) * string().map(build)
 }
 
-fn integer_command(
+fn integer_command<C: 'static>(
     command: &'static [u8],
-    build: &'static Fn(i32) -> Command,
-) -> Parser<u8, Command> {
+    build: &'static dyn Fn(i32) -> C,
+) -> Parser<u8, C> {
     start_command(command) * integer().map(build)
 }
 
-fn uinteger_command(
+fn uinteger_command<C: 'static>(
     command: &'static [u8],
-    build: &'static Fn(u32) -> Command,
-) -> Parser<u8, Command> {
+    build: &'static dyn Fn(u32) -> C,
+) -> Parser<u8, C> {
     start_command(command) * uinteger().map(build)
 }
 
-fn number_command(
+fn number_command<C: 'static>(
     command: &'static [u8],
-    build: &'static Fn(f64) -> Command,
-) -> Parser<u8, Command> {
+    build: &'static dyn Fn(f64) -> C,
+) -> Parser<u8, C> {
     start_command(command) * number().map(build)
 }
 
-fn bool_command(
+fn bool_command<C: 'static>(
     command: &'static [u8],
-    build: &'static Fn(bool) -> Command,
-) -> Parser<u8, Command> {
+    build: &'static dyn Fn(bool) -> C,
+) -> Parser<u8, C> {
     start_command(command) * boolean().map(build)
 }
 
-fn bbox_command(
+fn bbox_command<C: 'static>(
     command: &'static [u8],
-    build: &'static Fn(Bbox<f64>) -> Command,
-) -> Parser<u8, Command> {
+    build: &'static dyn Fn(Rect) -> C,
+) -> Parser<u8, C> {
     start_command(command) * bbox().map(build)
 }
 
-fn num_num_command(
+fn num_num_command<C: 'static>(
     command: &'static [u8],
-    build: &'static Fn(f64, f64) -> Command,
-) -> Parser<u8, Command> {
+    build: &'static dyn Fn(f64, f64) -> C,
+) -> Parser<u8, C> {
     start_command(command) * (number() - space() + number()).map(move |(a, b)| build(a, b))
 }
 
 // Kern Pairs
-fn kp_cmd() -> Parser<u8, KernPair> {
+fn kp_cmd() -> Parser<u8, KernPairRef<'static>> {
     let cmd = seq(b"KP") - space();
     let names = name() - space() + name() - space();
     let nums = number() - space() + number();
-    cmd * (names + nums).map(move |((n1, n2), (numx, numy))| KernPair {
-        first_kern_character: n1,
-        second_kern_character: n2,
+    cmd * (names + nums).map(move |((n1, n2), (numx, numy))| KernPairRef {
+        first_kern_character: Cow::Owned(n1),
+        second_kern_character: Cow::Owned(n2),
         x: numx,
         y: numy,
+        span: None,
     })
 }
 
@@ -338,115 +553,87 @@ fn hex_string() -> Parser<u8, String> {
     sym(b'<') * hex_char.repeat(0..).map(|v| v.into_iter().collect()) - sym(b'>')
 }
 
-fn kph_cmd() -> Parser<u8, KernPair> {
+fn kph_cmd() -> Parser<u8, KernPairRef<'static>> {
     let cmd = seq(b"KPH") - space();
     let names = hex_string() - space() + hex_string() - space();
     let nums = number() - space() + number();
-    cmd * (names + nums).map(move |((n1, n2), (numx, numy))| KernPair {
-        first_kern_character: n1,
-        second_kern_character: n2,
+    cmd * (names + nums).map(move |((n1, n2), (numx, numy))| KernPairRef {
+        first_kern_character: Cow::Owned(n1),
+        second_kern_character: Cow::Owned(n2),
         x: numx,
         y: numy,
+        span: None,
     })
 }
 
-fn kpx_cmd() -> Parser<u8, KernPair> {
+fn kpx_cmd() -> Parser<u8, KernPairRef<'static>> {
     let cmd = seq(b"KPX") - space();
     let names = name() - space() + name() - space();
     let num = number();
-    cmd * (names + num).map(|((name1, name2), num)| KernPair {
-        first_kern_character: name1,
-        second_kern_character: name2,
+    cmd * (names + num).map(|((name1, name2), num)| KernPairRef {
+        first_kern_character: Cow::Owned(name1),
+        second_kern_character: Cow::Owned(name2),
         x: num,
         y: 0.0,
+        span: None,
     })
 }
 
-fn kpy_cmd() -> Parser<u8, KernPair> {
+fn kpy_cmd() -> Parser<u8, KernPairRef<'static>> {
     let cmd = seq(b"KPY") - space();
     let names = name() - space() + name() - space();
     let num = number();
-    cmd * (names + num).map(|((name1, name2), num)| KernPair {
-        first_kern_character: name1,
-        second_kern_character: name2,
+    cmd * (names + num).map(|((name1, name2), num)| KernPairRef {
+        first_kern_character: Cow::Owned(name1),
+        second_kern_character: Cow::Owned(name2),
         x: 0.0,
         y: num,
+        span: None,
     })
 }
 
-fn kern_pair() -> Parser<u8, KernPair> {
+fn kern_pair() -> Parser<u8, KernPairRef<'static>> {
     kp_cmd() | kph_cmd() | kpx_cmd() | kpy_cmd()
 }
 
-fn kern_pairs() -> Parser<u8, (Option<u8>, Vec<KernPair>)> {
-    let command = seq(b"StartKernPairs") * one_of(b"01").opt();
-    command - space() + uinteger() - eol().repeat(1..) >> move |(idx, len)| {
-        let end = seq(b"EndKernPairs") - eol().repeat(1..);
-        let pairs = (kern_pair() - eol().repeat(1..)).repeat(len as usize);
-        pairs.map(move |pairs| (idx, pairs)) - end
-    }
-}
-
 fn track_kern() -> Parser<u8, TrackKern> {
     let begin = seq(b"TrackKern") - space();
     let num_space = || number() - space();
-    let content = integer() - space() + num_space() + num_space() + num_space() + num_space();
+    let content = integer() - space() + num_space() + num_space() + num_space() + number();
     begin
         * content.map(
             |((((deg, min_size), min_kern), max_size), max_kern)| TrackKern {
                 degree: deg,
                 min_point_size: min_size,
-                min_kern: min_kern,
+                min_kern,
                 max_point_size: max_size,
-                max_kern: max_kern,
+                max_kern,
             },
         )
 }
 
-fn track_kerns() -> Parser<u8, Vec<TrackKern>> {
-    let begin = (seq(b"StartTrackKern") - space()) * uinteger() - eol();
-    begin >> move |len| (track_kern() - eol()).repeat(len as usize) - seq(b"EndTrackKern") - eol()
-}
-
-fn kern_data() -> Parser<u8, Vec<KernDataCmd>> {
-    let kernpairs = kern_pairs().map(|(idx, pairs)| match idx {
-        Some(0) => KernDataCmd::Kernpairs0(pairs),
-        Some(1) => KernDataCmd::Kernpairs1(pairs),
-        Some(_) => unreachable!(),
-        None => KernDataCmd::Kernpairs(pairs),
-    });
-    let trackkern = track_kerns().map(|kerns| KernDataCmd::TrackKern(kerns));
-    let begin = seq(b"StartKernData") - eol().repeat(1..);
-    let content = (kernpairs | trackkern).repeat(1..);
-    begin * content - seq(b"EndKernData")
-}
-
 // Composites
 
-fn composite_part() -> Parser<u8, CompositePart> {
+fn composite_part() -> Parser<u8, CompositePartRef<'static>> {
     let params =
-        (name() - space() + integer() - space() + integer()).map(|((n, x), y)| CompositePart {
-            name: n,
+        (name() - space() + integer() - space() + integer()).map(|((n, x), y)| CompositePartRef {
+            name: Cow::Owned(n),
             x_displacement: x,
             y_displacement: y,
         });
     (seq(b"PCC") - space()) * params
 }
 
-fn composite() -> Parser<u8, Composite> {
-    (seq(b"CC") - space()) * name() - space() + uinteger() >> |(name, len): (String, u32)| {
-        (space() * composite_part())
-            .repeat(len as usize)
-            .map(move |parts| Composite {
-                name: name.to_owned(),
-                parts: parts,
-            })
-    }
-}
-
-fn composites() -> Parser<u8, Vec<Composite>> {
-    (seq(b"StartComposites") - space()) * uinteger() - eol().repeat(1..) >> move |len| {
-        (composite() - eol().repeat(1..)).repeat(len as usize) - seq(b"EndComposites")
+fn composite() -> Parser<u8, CompositeRef<'static>> {
+    // The parts are usually separated by `;`, as in `CC Aacute 2 ; PCC A 0 0 ;`.
+    let separator = || (space().opt() * sym(b';') * space().opt()).discard() | space();
+    (seq(b"CC") - space()) * name() - space() + uinteger() >> move |(name, len): (String, u32)| {
+        let parts = (separator() * composite_part()).repeat(len as usize);
+        (parts - (space().opt() * sym(b';')).opt()).map(move |parts| CompositeRef {
+            name: Cow::Owned(name.to_owned()),
+            parts,
+            span: None,
+        })
     }
 }
 
@@ -466,26 +653,26 @@ enum CharMetricCommand {
     W1(f64, f64),
     VV(f64, f64),
     N(String),
-    B(Bbox<f64>),
-    L(Ligature),
+    B(Rect),
+    L(LigatureRef<'static>),
 }
 
-fn ligature() -> Parser<u8, Ligature> {
-    (name() - space() + name()).map(|(s, l)| Ligature {
-        successor: s,
-        ligature: l,
+fn ligature() -> Parser<u8, LigatureRef<'static>> {
+    (name() - space() + name()).map(|(s, l)| LigatureRef {
+        successor: Cow::Owned(s),
+        ligature: Cow::Owned(l),
     })
 }
 
 fn charcommand() -> Parser<u8, CharMetricCommand> {
-    (sym(b'C') - space()) * integer().map(&CharMetricCommand::C)
-        | (seq(b"CH") - space()) * hex_integer().map(&CharMetricCommand::C)
-        | (seq(b"WX") - space()) * number().map(&CharMetricCommand::WX)
-        | (seq(b"W0X") - space()) * number().map(&CharMetricCommand::W0X)
-        | (seq(b"W1X") - space()) * number().map(&CharMetricCommand::W1X)
-        | (seq(b"WY") - space()) * number().map(&CharMetricCommand::WY)
-        | (seq(b"W0Y") - space()) * number().map(&CharMetricCommand::W0Y)
-        | (seq(b"W1Y") - space()) * number().map(&CharMetricCommand::W1Y)
+    (sym(b'C') - space()) * integer().map(CharMetricCommand::C)
+        | (seq(b"CH") - space()) * hex_integer().map(CharMetricCommand::C)
+        | (seq(b"WX") - space()) * number().map(CharMetricCommand::WX)
+        | (seq(b"W0X") - space()) * number().map(CharMetricCommand::W0X)
+        | (seq(b"W1X") - space()) * number().map(CharMetricCommand::W1X)
+        | (seq(b"WY") - space()) * number().map(CharMetricCommand::WY)
+        | (seq(b"W0Y") - space()) * number().map(CharMetricCommand::W0Y)
+        | (seq(b"W1Y") - space()) * number().map(CharMetricCommand::W1Y)
         | (seq(b"W") - space())
             * (number() - space() + number()).map(|(x, y)| CharMetricCommand::W(x, y))
         | (seq(b"W0") - space())
@@ -499,45 +686,29 @@ fn charcommand() -> Parser<u8, CharMetricCommand> {
         | (sym(b'L') - space()) * ligature().map(CharMetricCommand::L)
 }
 
-fn char_metric() -> Parser<u8, CharMetric> {
-    let seperator = || space().opt() * sym(b';') - space().opt();
-    let cmds = list(charcommand(), seperator()) - seperator().opt();
-    cmds.map(|commands| {
-        commands.into_iter().fold(
-            CharMetric::default(),
-            |mut metric: CharMetric, command: CharMetricCommand| {
-                match command {
-                    CharMetricCommand::C(c) => metric.character_code = c,
-                    CharMetricCommand::WX(wx) => metric.wx = wx,
-                    CharMetricCommand::W0X(w0x) => metric.w0x = w0x,
-                    CharMetricCommand::W1X(w1x) => metric.w1x = w1x,
-                    CharMetricCommand::WY(wy) => metric.wy = wy,
-                    CharMetricCommand::W0Y(w0y) => metric.w0y = w0y,
-                    CharMetricCommand::W1Y(w1y) => metric.w1y = w1y,
-                    CharMetricCommand::W(w1, w2) => metric.w = (w1, w2),
-                    CharMetricCommand::W0(w1, w2) => metric.w0 = (w1, w2),
-                    CharMetricCommand::W1(w1, w2) => metric.w1 = (w1, w2),
-                    CharMetricCommand::VV(vv1, vv2) => metric.vv = (vv1, vv2),
-                    CharMetricCommand::N(name) => metric.name = name,
-                    CharMetricCommand::B(bbox) => metric.bbox = bbox,
-                    CharMetricCommand::L(lig) => metric.ligatures.push(lig),
-                }
-                metric
-            },
-        )
-    })
-}
-
-fn char_metrics() -> Parser<u8, Vec<CharMetric>> {
-    let begin = seq(b"StartCharMetrics") * space() * uinteger() - eol().repeat(1..);
-    begin >> move |len: u32| {
-        (char_metric() - eol().repeat(1..)).repeat(len as usize) - seq(b"EndCharMetrics")
+impl<'a> CharMetricRef<'a> {
+    fn apply(&mut self, command: CharMetricCommand) {
+        match command {
+            CharMetricCommand::C(c) => self.character_code = c,
+            CharMetricCommand::WX(wx) => self.wx = wx,
+            CharMetricCommand::W0X(w0x) => self.w0x = w0x,
+            CharMetricCommand::W1X(w1x) => self.w1x = w1x,
+            CharMetricCommand::WY(wy) => self.wy = wy,
+            CharMetricCommand::W0Y(w0y) => self.w0y = w0y,
+            CharMetricCommand::W1Y(w1y) => self.w1y = w1y,
+            CharMetricCommand::W(w1, w2) => self.w = (w1, w2),
+            CharMetricCommand::W0(w1, w2) => self.w0 = (w1, w2),
+            CharMetricCommand::W1(w1, w2) => self.w1 = (w1, w2),
+            CharMetricCommand::VV(vv1, vv2) => self.vv = (vv1, vv2),
+            CharMetricCommand::N(name) => self.name = Cow::Owned(name),
+            CharMetricCommand::B(bbox) => self.bbox = bbox,
+            CharMetricCommand::L(lig) => self.ligatures.push(lig),
+        }
     }
 }
 
 fn comment() -> Parser<u8, Command> {
-    let cmd =
-        (seq(b"Comment") - space()) * string().opt().map(|o| o.unwrap_or_else(|| String::new()));
+    let cmd = seq(b"Comment") * (space() * string()).opt().map(Option::unwrap_or_default);
     cmd.map(Command::Comment)
 }
 
@@ -545,13 +716,12 @@ fn comment() -> Parser<u8, Command> {
 
 #[derive(PartialEq, Debug)]
 enum Command {
-    //    AfmVersion((i32, i32)),
     MetricsSet(i32),
     FontName(String),
     FullName(String),
     FamilyName(String),
     Weight(String),
-    FontBBox(Bbox<f64>),
+    FontBBox(Rect),
     Version(String),
     Notice(String),
     EncodingScheme(String),
@@ -569,115 +739,1347 @@ enum Command {
     StdHW(f64),
     StdVW(f64),
     Comment(String),
+    Extra(String, String),
+    Direction(u8, Vec<DirectionCmd>),
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum DirectionCmd {
     UnderlinePosition(f64),
     UnderlineThickness(f64),
     ItalicAngle(f64),
     CharWidth(f64, f64),
     IsFixedPitch(bool),
-    CharMetrics(Vec<CharMetric>),
-    Composites(Vec<Composite>),
-    KernData(Vec<KernDataCmd>),
 }
 
-#[derive(PartialEq, Debug)]
-enum KernDataCmd {
-    TrackKern(Vec<TrackKern>),
-    Kernpairs(Vec<KernPair>),
-    Kernpairs0(Vec<KernPair>),
-    Kernpairs1(Vec<KernPair>),
-}
-
-fn command<'a>() -> Parser<u8, Command> {
-    comment() | string_command(b"Version", &Command::Version)
-        | integer_command(b"MetricsSet", &Command::MetricsSet)
-        | string_command(b"FontName", &Command::FontName)
-        | string_command(b"FullName", &Command::FullName)
-        | string_command(b"FamilyName", &Command::FamilyName)
-        | string_command(b"Weight", &Command::Weight)
-        | bbox_command(b"FontBBox", &Command::FontBBox)
-        | string_command(b"Weight", &Command::Weight)
-        | string_command(b"Version", &Command::Version)
-        | string_command(b"Notice", &Command::Notice)
-        | string_command(b"EncodingScheme", &Command::EncodingScheme)
-        | uinteger_command(b"MappingScheme", &Command::MappingScheme)
-        | uinteger_command(b"EscChar", &Command::EscChar)
-        | string_command(b"CharacterSet", &Command::CharacterSet)
-        | uinteger_command(b"Characters", &Command::Characters)
-        | bool_command(b"IsBaseFont", &Command::IsBaseFont)
-        | num_num_command(b"VVector", &Command::VVector)
-        | bool_command(b"IsFixedV", &Command::IsFixedV)
-        | number_command(b"CapHeight", &Command::CapHeight)
-        | number_command(b"XHeight", &Command::XHeight)
-        | number_command(b"Ascender", &Command::Ascender)
-        | number_command(b"Descender", &Command::Descender)
-        | number_command(b"StdHW", &Command::StdHW) | number_command(b"StdVW", &Command::StdVW)
-        | number_command(b"UnderlinePosition", &Command::UnderlinePosition)
-        | number_command(b"UnderlineThickness", &Command::UnderlineThickness)
-        | number_command(b"ItalicAngle", &Command::ItalicAngle)
-        | num_num_command(b"CharWidth", &Command::CharWidth)
-        | bool_command(b"IsFixedPitch", &Command::IsFixedPitch)
-        | char_metrics().map(Command::CharMetrics) | composites().map(Command::Composites)
-        | kern_data().map(Command::KernData)
+/// The parser for a single-line command, or `None` if `keyword` is unknown.
+fn command(keyword: &[u8]) -> Option<Parser<u8, Command>> {
+    let parser = match keyword {
+        b"Comment" => comment(),
+        b"Version" => string_command(b"Version", &Command::Version),
+        b"MetricsSets" => integer_command(b"MetricsSets", &Command::MetricsSet),
+        b"MetricsSet" => integer_command(b"MetricsSet", &Command::MetricsSet),
+        b"FontName" => string_command(b"FontName", &Command::FontName),
+        b"FullName" => string_command(b"FullName", &Command::FullName),
+        b"FamilyName" => string_command(b"FamilyName", &Command::FamilyName),
+        b"Weight" => string_command(b"Weight", &Command::Weight),
+        b"FontBBox" => bbox_command(b"FontBBox", &Command::FontBBox),
+        b"Notice" => string_command(b"Notice", &Command::Notice),
+        b"EncodingScheme" => string_command(b"EncodingScheme", &Command::EncodingScheme),
+        b"MappingScheme" => uinteger_command(b"MappingScheme", &Command::MappingScheme),
+        b"EscChar" => uinteger_command(b"EscChar", &Command::EscChar),
+        b"CharacterSet" => string_command(b"CharacterSet", &Command::CharacterSet),
+        b"Characters" => uinteger_command(b"Characters", &Command::Characters),
+        b"IsBaseFont" => bool_command(b"IsBaseFont", &Command::IsBaseFont),
+        b"VVector" => num_num_command(b"VVector", &Command::VVector),
+        b"IsFixedV" => bool_command(b"IsFixedV", &Command::IsFixedV),
+        b"CapHeight" => number_command(b"CapHeight", &Command::CapHeight),
+        b"XHeight" => number_command(b"XHeight", &Command::XHeight),
+        b"Ascender" => number_command(b"Ascender", &Command::Ascender),
+        b"Descender" => number_command(b"Descender", &Command::Descender),
+        b"StdHW" => number_command(b"StdHW", &Command::StdHW),
+        b"StdVW" => number_command(b"StdVW", &Command::StdVW),
+        _ => {
+            // Direction metrics outside of a `StartDirection` block apply to direction 0.
+            let parser = direction_command(keyword)?;
+            parser.map(|cmd| Command::Direction(0, vec![cmd]))
+        }
+    };
+    Some(parser)
 }
 
-// Public functions
+/// The parser for a writing direction command, or `None` if `keyword` is unknown.
+fn direction_command(keyword: &[u8]) -> Option<Parser<u8, DirectionCmd>> {
+    let parser = match keyword {
+        b"UnderlinePosition" => {
+            number_command(b"UnderlinePosition", &DirectionCmd::UnderlinePosition)
+        }
+        b"UnderlineThickness" => {
+            number_command(b"UnderlineThickness", &DirectionCmd::UnderlineThickness)
+        }
+        b"ItalicAngle" => number_command(b"ItalicAngle", &DirectionCmd::ItalicAngle),
+        b"CharWidth" => num_num_command(b"CharWidth", &DirectionCmd::CharWidth),
+        b"IsFixedPitch" => bool_command(b"IsFixedPitch", &DirectionCmd::IsFixedPitch),
+        _ => return None,
+    };
+    Some(parser)
+}
 
-pub fn afm() -> Parser<u8, FontMetrics> {
-    let begin =
-        start_command(b"StartFontMetrics") * (is_a(digit) - sym(b'.') + is_a(digit)) - eol();
-    let end = eol().opt() * seq(b"EndFontMetrics") * eol().repeat(0..) * end();
-    let elems = list(command(), eol());
-
-    let commands = begin * elems.expect("AFM commands") - end.expect("EndFontMetrics");
-    commands.map(|commands| {
-        commands.into_iter().fold(
-            FontMetrics::default(),
-            |mut metric: FontMetrics, command: Command| {
-                match command {
-                    Command::MetricsSet(metric_sets) => metric.metric_sets = metric_sets,
-                    Command::FontName(name) => metric.font_name = name,
-                    Command::FullName(name) => metric.full_name = name,
-                    Command::FamilyName(name) => metric.family_name = name,
-                    Command::Weight(weight) => metric.weight = weight,
-                    Command::FontBBox(bbox) => metric.font_bbox = bbox,
-                    Command::Version(version) => metric.font_version = version,
-                    Command::Notice(notice) => metric.notice = notice,
-                    Command::EncodingScheme(scheme) => metric.encoding_scheme = scheme,
-                    Command::MappingScheme(scheme) => metric.mapping_scheme = scheme,
-                    Command::EscChar(c) => metric.esc_char = c,
-                    Command::CharacterSet(charset) => metric.character_set = charset,
-                    Command::Characters(c) => metric.characters = c,
-                    Command::IsBaseFont(base_font) => metric.is_base_font = base_font,
-                    Command::VVector(v1, v2) => metric.v_vector = (v1, v2),
-                    Command::IsFixedV(fixed) => metric.is_fixed_v = fixed,
-                    Command::CapHeight(height) => metric.cap_height = height,
-                    Command::XHeight(height) => metric.x_height = height,
-                    Command::Ascender(asc) => metric.ascender = asc,
-                    Command::Descender(desc) => metric.descender = desc,
-                    Command::StdHW(stdhw) => metric.standard_horizontal_width = stdhw,
-                    Command::StdVW(stdvw) => metric.standard_vertical_width = stdvw,
-                    Command::Comment(comment) => metric.comments.push(comment),
-                    Command::UnderlinePosition(pos) => metric.underline_position = pos,
-                    Command::UnderlineThickness(thickness) => {
-                        metric.underline_thickness = thickness
+// Lines
+
+/// Keys that may start an entry of the given section.
+static CHAR_METRIC_KEYS: &[&[u8]] = &[
+    b"C", b"CH", b"WX", b"W0X", b"W1X", b"WY", b"W0Y", b"W1Y", b"W", b"W0", b"W1", b"VV", b"N",
+    b"B", b"L",
+];
+static KERN_PAIR_KEYS: &[&[u8]] = &[b"KP", b"KPH", b"KPX", b"KPY"];
+static TRACK_KERN_KEYS: &[&[u8]] = &[b"TrackKern"];
+static COMPOSITE_KEYS: &[&[u8]] = &[b"CC"];
+/// The keys of master and composite font files beyond those of font
+/// metrics.
+static FONT_FILE_KEYS: &[&[u8]] = &[
+    b"Axes", b"AxisLabel", b"AxisType", b"BlendAxisTypes", b"BlendDesignMap",
+    b"BlendDesignPositions", b"Descendants", b"Masters", b"PC", b"PL", b"PN", b"WeightVector",
+];
+
+/// A line of the input without its line break. The text is borrowed when
+/// parsing a byte slice and owned when reading from a stream.
+#[derive(Clone)]
+struct Line<'a> {
+    number: usize,
+    offset: usize,
+    text: Cow<'a, [u8]>,
+}
+
+/// The name starting at `column` of `text` after skipping spaces and `;`
+/// separators, together with the column it starts at.
+fn token(text: &[u8], column: usize) -> (usize, &[u8]) {
+    let start = column
+        + text[column..]
+            .iter()
+            .take_while(|&&c| char_class::space(c) || c == b';')
+            .count();
+    let len = text[start..].iter().take_while(|&&c| name_char(c)).count();
+    (start, &text[start..start + len])
+}
+
+impl<'a> Line<'a> {
+    fn is_blank(&self) -> bool {
+        self.text.iter().all(|&c| char_class::space(c))
+    }
+
+    /// The name starting at `column` after skipping spaces and `;` separators,
+    /// together with the column it starts at.
+    fn token(&self, column: usize) -> (usize, &[u8]) {
+        token(&self.text, column)
+    }
+
+    fn position(&self, column: usize) -> Position {
+        Position {
+            line: self.number,
+            column: column + 1,
+            offset: self.offset + column,
+        }
+    }
+
+    fn span(&self) -> Span {
+        Span {
+            line: self.number,
+            start: self.offset,
+            end: self.offset + self.text.len(),
+        }
+    }
+
+    /// Runs `parser` from `column` on, requiring it to consume the rest of
+    /// the line except trailing spaces. Fails with the column it stopped at.
+    fn parse<O>(&self, parser: &Parser<u8, O>, column: usize) -> Result<O, usize> {
+        self.parse_span(parser, column, self.text.len())
+    }
+
+    /// Like `parse`, but only up to the column `end`.
+    fn parse_span<O>(&self, parser: &Parser<u8, O>, column: usize, end: usize) -> Result<O, usize> {
+        let mut input = DataInput::new(&self.text[column..end]);
+        match parser.parse(&mut input) {
+            Ok(value) => {
+                let rest = column + input.position;
+                if self.text[rest..end].iter().all(|&c| char_class::space(c)) {
+                    Ok(value)
+                } else {
+                    Err(rest)
+                }
+            }
+            Err(err) => Err(column + error_position(&err).unwrap_or(0)),
+        }
+    }
+}
+
+fn error_position(err: &pom::Error) -> Option<usize> {
+    match *err {
+        pom::Error::Incomplete => None,
+        pom::Error::Mismatch { position, .. }
+        | pom::Error::Conversion { position, .. }
+        | pom::Error::Expect { position, .. }
+        | pom::Error::Custom { position, .. } => Some(position),
+    }
+}
+
+/// The UTF-8 byte order mark, which may start an AFM file.
+const BYTE_ORDER_MARK: &[u8] = b"\xEF\xBB\xBF";
+
+/// Where `Lines` reads from.
+enum Source<'a> {
+    Data(&'a [u8]),
+    Reader(&'a mut dyn BufRead),
+}
+
+/// Splits the input at `\n`, `\r\n` or `\r`, and collects the warnings of
+/// a lenient parse.
+struct Lines<'a> {
+    source: Source<'a>,
+    offset: usize,
+    number: usize,
+    /// A line handed back by `rewind`.
+    pending: Option<Line<'a>>,
+    /// The error that ended reading from a stream early.
+    error: Option<io::Error>,
+    /// The limit that ended reading early, with the start of the line
+    /// exceeding it.
+    exceeded: Option<(Limit, Position)>,
+    strict: bool,
+    limits: ParseLimits,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(data: &'a [u8], options: &ParseOptions) -> Lines<'a> {
+        Lines::with_source(Source::Data(data), options)
+    }
+
+    /// Lines read one at a time from `reader`, so the input is never held
+    /// in memory as a whole.
+    fn from_reader(reader: &'a mut dyn BufRead, options: &ParseOptions) -> Lines<'a> {
+        Lines::with_source(Source::Reader(reader), options)
+    }
+
+    fn with_source(source: Source<'a>, options: &ParseOptions) -> Lines<'a> {
+        Lines {
+            source,
+            offset: 0,
+            number: 0,
+            pending: None,
+            error: None,
+            exceeded: None,
+            strict: options.strict,
+            limits: options.limits,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Fails with `err` in strict mode, otherwise records it as a warning.
+    /// Exceeded limits always fail.
+    fn recover(&mut self, err: AfmError) -> Result<(), AfmError> {
+        if self.strict || matches!(err, AfmError::LimitExceeded { .. }) {
+            return Err(err);
+        }
+        self.warnings.push(err);
+        Ok(())
+    }
+
+    /// Records `warning` in either mode, for problems that never fail.
+    fn warn(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    /// Makes `line` the next line to be read again.
+    fn rewind(&mut self, line: &Line<'a>) {
+        self.pending = Some(line.clone());
+    }
+
+    /// The next line that is not blank, failing as part of `section` if
+    /// the input exceeds a limit before it.
+    fn next_line(&mut self, section: Section) -> Result<Option<Line<'a>>, AfmError> {
+        let line = self.find(|line| !line.is_blank());
+        match self.exceeded {
+            Some((limit, position)) => Err(AfmError::LimitExceeded {
+                limit,
+                max: self.limits.max(limit),
+                section,
+                position,
+            }),
+            None => Ok(line),
+        }
+    }
+
+    /// Fails if `count` entries of `line` exceed `limit`.
+    fn check_limit(
+        &self,
+        limit: Limit,
+        count: usize,
+        line: &Line,
+        section: Section,
+    ) -> Result<(), AfmError> {
+        let max = self.limits.max(limit);
+        if count <= max {
+            return Ok(());
+        }
+        Err(AfmError::LimitExceeded {
+            limit,
+            max,
+            section,
+            position: line.position(line.token(0).0),
+        })
+    }
+
+    /// The position just past the last line read.
+    fn end_position(&self) -> Position {
+        Position {
+            line: self.number + 1,
+            column: 1,
+            offset: self.offset,
+        }
+    }
+}
+
+/// The index of the first `\r` or `\n` of `text`. Looks at 8 bytes at a
+/// time up to the one holding it, since most of the input is lines.
+fn line_break(text: &[u8]) -> Option<usize> {
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    // Whether a byte of `word` is zero.
+    let has_zero = |word: u64| word.wrapping_sub(ONES) & !word & (ONES << 7) != 0;
+    let (cr, lf) = (ONES * u64::from(b'\r'), ONES * u64::from(b'\n'));
+    let mut start = 0;
+    for chunk in text.chunks_exact(8) {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(chunk);
+        let word = u64::from_ne_bytes(bytes);
+        if has_zero(word ^ cr) || has_zero(word ^ lf) {
+            break;
+        }
+        start += 8;
+    }
+    let len = text[start..].iter().position(|&c| c == b'\r' || c == b'\n');
+    len.map(|len| start + len)
+}
+
+/// Reads a line from `reader` into `text` without its line break. Returns
+/// the number of bytes consumed, which is 0 at the end of the input. Stops
+/// early once `text` is longer than `max`.
+fn read_line(reader: &mut dyn BufRead, text: &mut Vec<u8>, max: usize) -> io::Result<usize> {
+    let mut consumed = 0;
+    loop {
+        if text.len() > max {
+            return Ok(consumed);
+        }
+        let (end, used) = {
+            let buf = match reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if buf.is_empty() {
+                return Ok(consumed);
+            }
+            match line_break(buf) {
+                Some(len) => {
+                    text.extend_from_slice(&buf[..len]);
+                    (Some(buf[len]), len + 1)
+                }
+                None => {
+                    text.extend_from_slice(buf);
+                    (None, buf.len())
+                }
+            }
+        };
+        reader.consume(used);
+        consumed += used;
+        match end {
+            Some(b'\r') => {
+                if reader.fill_buf()?.first() == Some(&b'\n') {
+                    reader.consume(1);
+                    consumed += 1;
+                }
+                return Ok(consumed);
+            }
+            Some(_) => return Ok(consumed),
+            None => {}
+        }
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Line<'a>> {
+        if let Some(line) = self.pending.take() {
+            return Some(line);
+        }
+        if self.exceeded.is_some() {
+            return None;
+        }
+        let (text, consumed) = match self.source {
+            Source::Data(data) => {
+                if self.offset >= data.len() {
+                    return None;
+                }
+                let rest = &data[self.offset..];
+                let len = line_break(rest).unwrap_or(rest.len());
+                let consumed = if rest[len..].starts_with(b"\r\n") {
+                    len + 2
+                } else {
+                    (len + 1).min(rest.len())
+                };
+                (Cow::Borrowed(&rest[..len]), consumed)
+            }
+            Source::Reader(ref mut reader) => {
+                if self.error.is_some() {
+                    return None;
+                }
+                let mut text = Vec::new();
+                match read_line(*reader, &mut text, self.limits.max_line_length) {
+                    Ok(0) => return None,
+                    Ok(consumed) => (Cow::Owned(text), consumed),
+                    Err(err) => {
+                        self.error = Some(err);
+                        return None;
                     }
-                    Command::ItalicAngle(angle) => metric.italic_angle = angle,
-                    Command::CharWidth(w1, w2) => metric.char_width = (w1, w2),
-                    Command::IsFixedPitch(fixed) => metric.is_fixed_pitch = fixed,
-                    Command::CharMetrics(char_metrics) => metric.char_metrics = char_metrics,
-                    Command::Composites(composites) => metric.composites = composites,
-                    Command::KernData(cmds) => for cmd in cmds {
-                        match cmd {
-                            KernDataCmd::TrackKern(kerns) => metric.track_kern = kerns,
-                            KernDataCmd::Kernpairs(pairs) => metric.kern_pairs = pairs,
-                            KernDataCmd::Kernpairs0(pairs) => metric.kern_pairs0 = pairs,
-                            KernDataCmd::Kernpairs1(pairs) => metric.kern_pairs1 = pairs,
-                        }
-                    },
                 }
-                metric
+            }
+        };
+        let mut line = Line {
+            number: self.number + 1,
+            offset: self.offset,
+            text,
+        };
+        if self.number == 0 && line.text.starts_with(BYTE_ORDER_MARK) {
+            // A UTF-8 byte order mark is not part of the first line.
+            let len = BYTE_ORDER_MARK.len();
+            line.offset += len;
+            line.text = match line.text {
+                Cow::Borrowed(text) => Cow::Borrowed(&text[len..]),
+                Cow::Owned(mut text) => {
+                    text.drain(..len);
+                    Cow::Owned(text)
+                }
+            };
+        }
+        let limit = if line.text.len() > self.limits.max_line_length {
+            Some(Limit::LineLength)
+        } else if consumed > self.limits.max_bytes - self.offset {
+            Some(Limit::Bytes)
+        } else {
+            None
+        };
+        if let Some(limit) = limit {
+            self.exceeded = Some((limit, line.position(0)));
+            return None;
+        }
+        self.number += 1;
+        self.offset += consumed;
+        Some(line)
+    }
+}
+
+// Sections
+
+fn unexpected_keyword(keyword: &[u8], section: Section, position: Position) -> AfmError {
+    AfmError::UnexpectedKeyword {
+        keyword: String::from_utf8_lossy(keyword).into_owned(),
+        section,
+        position,
+    }
+}
+
+fn invalid_value(keyword: &[u8], section: Section, position: Position) -> AfmError {
+    AfmError::InvalidValue {
+        keyword: String::from_utf8_lossy(keyword).into_owned(),
+        section,
+        position,
+    }
+}
+
+/// The value following the keyword of `line`.
+fn keyword_value<O: 'static>(
+    line: &Line,
+    value: Parser<u8, O>,
+    section: Section,
+) -> Result<O, AfmError> {
+    let (column, keyword) = line.token(0);
+    line.parse(&(space() * value), column + keyword.len())
+        .map_err(|failed| invalid_value(keyword, section, line.position(failed)))
+}
+
+/// Whether `section` spans a whole file rather than being nested in one.
+fn is_file_section(section: Section) -> bool {
+    matches!(
+        section,
+        Section::FontMetrics | Section::MasterFontMetrics | Section::CompFontMetrics
+    )
+}
+
+/// Hands each line up to the end keyword of `section` to `entry`.
+///
+/// In lenient mode lines failing `entry` are skipped, and the section also
+/// ends at the end of the input or at the end keyword of an enclosing one.
+fn block<'a, F>(lines: &mut Lines<'a>, section: Section, mut entry: F) -> Result<(), AfmError>
+where
+    F: FnMut(&mut Lines<'a>, &Line<'a>) -> Result<(), AfmError>,
+{
+    loop {
+        let line = match lines.next_line(section)? {
+            Some(line) => line,
+            None => {
+                let position = lines.end_position();
+                return lines.recover(AfmError::MissingEnd { section, position });
+            }
+        };
+        let (column, keyword) = line.token(0);
+        if keyword == section.end_keyword().as_bytes() {
+            return Ok(());
+        }
+        if keyword.starts_with(b"End") {
+            let position = line.position(column);
+            lines.recover(AfmError::MissingEnd { section, position })?;
+            // A nested section ends at the end of an enclosing one, while a
+            // stray end keyword in a file is skipped.
+            if !is_file_section(section) {
+                lines.rewind(&line);
+                return Ok(());
+            }
+            continue;
+        }
+        if let Err(err) = entry(lines, &line) {
+            lines.recover(err)?;
+        }
+    }
+}
+
+/// Checks that the input starts with the start keyword of `section` and
+/// returns the version following it.
+fn start_line(lines: &mut Lines, section: Section) -> Result<AfmVersion, AfmError> {
+    let start = lines.next_line(section)?.ok_or_else(|| AfmError::MissingStart {
+        section,
+        position: lines.end_position(),
+    })?;
+    let (column, keyword) = start.token(0);
+    if keyword != section.start_keyword().as_bytes() {
+        return Err(AfmError::MissingStart {
+            section,
+            position: start.position(column),
+        });
+    }
+    match keyword_value(&start, version(), section) {
+        Ok(version) => Ok(version),
+        Err(err) => {
+            lines.recover(err)?;
+            Ok(AfmVersion::default())
+        }
+    }
+}
+
+/// Checks that nothing but blank lines follow the end of the input.
+fn end_of_input(lines: &mut Lines, section: Section) -> Result<(), AfmError> {
+    while let Some(line) = lines.next_line(section)? {
+        let (column, keyword) = line.token(0);
+        lines.recover(unexpected_keyword(keyword, section, line.position(column)))?;
+    }
+    Ok(())
+}
+
+/// Checks a count declared by a keyword like `Masters` against the parsed entries.
+fn check_count(
+    lines: &mut Lines,
+    declared: Option<(u32, Position)>,
+    found: usize,
+    section: Section,
+) -> Result<(), AfmError> {
+    match declared {
+        Some((declared, position)) if declared as usize != found => {
+            lines.recover(AfmError::CountMismatch {
+                section,
+                declared,
+                found: found as u32,
+                position,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Hands the entries of `section` up to its end keyword, one per line, to
+/// `entry`, and checks their number against the count following the
+/// keyword of `start`. Entries that fail are skipped in lenient mode, but
+/// still counted, as they are reported already.
+fn visit_section<'a, F>(
+    lines: &mut Lines<'a>,
+    start: &Line,
+    section: Section,
+    mut entry: F,
+) -> Result<(), AfmError>
+where
+    F: FnMut(&mut Lines<'a>, &Line<'a>) -> Result<(), AfmError>,
+{
+    let declared = match keyword_value(start, uinteger(), section) {
+        Ok(count) => Some((count, start.position(start.token(0).0))),
+        Err(err) => {
+            lines.recover(err)?;
+            None
+        }
+    };
+    let mut found = 0;
+    block(lines, section, |lines, line| {
+        found += 1;
+        entry(lines, line)
+    })?;
+    check_count(lines, declared, found, section)
+}
+
+/// Reads the entries of `section` up to its end keyword, one per line.
+/// Entries that fail to parse are skipped in lenient mode.
+fn section<'a, O, F>(
+    lines: &mut Lines<'a>,
+    start: &Line,
+    section: Section,
+    mut entry: F,
+) -> Result<Vec<O>, AfmError>
+where
+    F: FnMut(&mut Lines<'a>, &Line<'a>) -> Result<O, AfmError>,
+{
+    let mut entries = Vec::new();
+    visit_section(lines, start, section, |lines, line| {
+        entries.push(entry(lines, line)?);
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// A section entry parsed by `parser`, which must consume the whole line.
+fn line_entry<O: 'static>(
+    parser: Parser<u8, O>,
+    section: Section,
+    keys: &'static [&'static [u8]],
+) -> impl FnMut(&mut Lines, &Line) -> Result<O, AfmError> {
+    move |_, line| parse_entry(line, &parser, section, keys)
+}
+
+fn parse_entry<O>(
+    line: &Line,
+    parser: &Parser<u8, O>,
+    section: Section,
+    keys: &[&[u8]],
+) -> Result<O, AfmError> {
+    line.parse(parser, line.token(0).0)
+        .map_err(|_| entry_error(line, section, keys))
+}
+
+/// The error for an entry of `section` that failed to parse.
+fn entry_error(line: &Line, section: Section, keys: &[&[u8]]) -> AfmError {
+    let (column, keyword) = line.token(0);
+    if keys.contains(&keyword) {
+        invalid_value(keyword, section, line.position(column))
+    } else {
+        unexpected_keyword(keyword, section, line.position(column))
+    }
+}
+
+/// Parses a `C ... ;` line key by key. Unknown keys are kept, see
+/// `is_extra_key`, and in lenient mode known keys that fail to parse are
+/// skipped.
+fn char_metric<'a>(
+    lines: &mut Lines,
+    line: &Line,
+    command: &Parser<u8, CharMetricCommand>,
+) -> Result<CharMetricRef<'a>, AfmError> {
+    let mut metric = CharMetricRef::default();
+    let mut start = 0;
+    for segment in line.text.split(|&c| c == b';') {
+        let end = start + segment.len();
+        if !segment.iter().all(|&c| char_class::space(c)) {
+            let (column, keyword) = line.token(start);
+            match line.parse_span(command, column, end) {
+                Ok(command) => metric.apply(command),
+                Err(_) if is_extra_key(lines, keyword, CHAR_METRIC_KEYS) => {
+                    let (key, value) = extra_key(line, column, end);
+                    metric.extra_keys.push((Cow::Owned(key), Cow::Owned(value)));
+                }
+                Err(_) => {
+                    let position = line.position(column);
+                    lines.recover(if CHAR_METRIC_KEYS.contains(&keyword) {
+                        invalid_value(keyword, Section::CharMetrics, position)
+                    } else {
+                        unexpected_keyword(keyword, Section::CharMetrics, position)
+                    })?;
+                }
+            }
+        }
+        start = end + 1;
+    }
+    Ok(metric)
+}
+
+/// Reads a `StartDirection` block, which must be allowed by `metric_sets`,
+/// parsing its lines with `entry`.
+fn direction<'a, F>(
+    lines: &mut Lines<'a>,
+    start: &Line,
+    metric_sets: i32,
+    mut entry: F,
+) -> Result<Command, AfmError>
+where
+    F: FnMut(&Line<'a>) -> Result<DirectionCmd, AfmError>,
+{
+    let value = one_of(b"012").map(|d| d - b'0');
+    let direction = match keyword_value(start, value, Section::FontMetrics) {
+        Ok(direction) => direction,
+        Err(err) => {
+            lines.recover(err)?;
+            0
+        }
+    };
+    let allowed = match metric_sets {
+        0 => direction == 0,
+        1 => direction == 1,
+        _ => true,
+    };
+    if !allowed {
+        let (column, keyword) = start.token(0);
+        let position = start.position(start.token(column + keyword.len()).0);
+        lines.recover(invalid_value(keyword, Section::FontMetrics, position))?;
+    }
+
+    let mut cmds = Vec::new();
+    block(lines, Section::Direction, |_, line| {
+        cmds.push(entry(line)?);
+        Ok(())
+    })?;
+    Ok(Command::Direction(direction, cmds))
+}
+
+fn direction_entry(line: &Line) -> Result<DirectionCmd, AfmError> {
+    let (column, keyword) = line.token(0);
+    let parser = direction_command(keyword)
+        .ok_or_else(|| unexpected_keyword(keyword, Section::Direction, line.position(column)))?;
+    line.parse(&parser, column)
+        .map_err(|failed| invalid_value(keyword, Section::Direction, line.position(failed)))
+}
+
+/// Parses a line holding one of the keys accepted by `command()`. Other
+/// keys are kept as they are, see `is_extra_key`.
+fn header_command(lines: &Lines, line: &Line, section: Section) -> Result<Command, AfmError> {
+    let (column, keyword) = line.token(0);
+    match command(keyword) {
+        Some(parser) => line
+            .parse(&parser, column)
+            .map_err(|failed| invalid_value(keyword, section, line.position(failed))),
+        None if is_extra_key(lines, keyword, &[]) => {
+            let (key, value) = extra_key(line, column, line.text.len());
+            Ok(Command::Extra(key, value))
+        }
+        None => Err(unexpected_keyword(keyword, section, line.position(column))),
+    }
+}
+
+/// Whether `keyword`, which is not one of `keys`, is kept as an extra key:
+/// always if the format has no such keyword, as readers are to ignore
+/// unknown keys, and in lenient mode also if it is misplaced.
+fn is_extra_key(lines: &Lines, keyword: &[u8], keys: &[&[u8]]) -> bool {
+    !keyword.is_empty() && !keys.contains(&keyword) && (!lines.strict || !is_keyword(keyword))
+}
+
+/// Whether `keyword` belongs to the format somewhere in a file.
+fn is_keyword(keyword: &[u8]) -> bool {
+    keyword.starts_with(b"Start")
+        || keyword.starts_with(b"End")
+        || command(keyword).is_some()
+        || direction_command(keyword).is_some()
+        || [
+            CHAR_METRIC_KEYS,
+            KERN_PAIR_KEYS,
+            TRACK_KERN_KEYS,
+            COMPOSITE_KEYS,
+            FONT_FILE_KEYS,
+        ]
+        .iter()
+        .any(|keys| keys.contains(&keyword))
+}
+
+/// The key at `column` and the trimmed value following it up to `end`.
+fn extra_key(line: &Line, column: usize, end: usize) -> (String, String) {
+    let keyword = line.token(column).1;
+    let value = &line.text[column + keyword.len()..end];
+    (
+        String::from_utf8_lossy(keyword).into_owned(),
+        decode_text(value).trim().to_string(),
+    )
+}
+
+// Glyphs
+
+/// The parsers for the lines of an AFM file: the pom parsers above for
+/// `afm()`, and hand-written ones for `FontMetrics::parse`.
+trait Grammar<'a> {
+    fn header_command(
+        &self,
+        lines: &Lines<'a>,
+        line: &Line<'a>,
+        section: Section,
+    ) -> Result<Command, AfmError>;
+
+    fn direction_entry(&self, line: &Line<'a>) -> Result<DirectionCmd, AfmError>;
+
+    fn char_metric(
+        &self,
+        lines: &mut Lines<'a>,
+        line: &Line<'a>,
+    ) -> Result<CharMetricRef<'a>, AfmError>;
+
+    fn kern_pair(&self, line: &Line<'a>) -> Result<KernPairRef<'a>, AfmError>;
+
+    fn composite(&self, line: &Line<'a>) -> Result<CompositeRef<'a>, AfmError>;
+}
+
+struct Pom {
+    charcommand: Parser<u8, CharMetricCommand>,
+    kern_pair: Parser<u8, KernPairRef<'static>>,
+    composite: Parser<u8, CompositeRef<'static>>,
+}
+
+impl Pom {
+    fn new() -> Pom {
+        Pom {
+            charcommand: charcommand(),
+            kern_pair: kern_pair(),
+            composite: composite(),
+        }
+    }
+}
+
+impl<'a> Grammar<'a> for Pom {
+    fn header_command(
+        &self,
+        lines: &Lines<'a>,
+        line: &Line<'a>,
+        section: Section,
+    ) -> Result<Command, AfmError> {
+        header_command(lines, line, section)
+    }
+
+    fn direction_entry(&self, line: &Line<'a>) -> Result<DirectionCmd, AfmError> {
+        direction_entry(line)
+    }
+
+    fn char_metric(
+        &self,
+        lines: &mut Lines<'a>,
+        line: &Line<'a>,
+    ) -> Result<CharMetricRef<'a>, AfmError> {
+        char_metric(lines, line, &self.charcommand)
+    }
+
+    fn kern_pair(&self, line: &Line<'a>) -> Result<KernPairRef<'a>, AfmError> {
+        parse_entry(line, &self.kern_pair, Section::KernPairs, KERN_PAIR_KEYS)
+    }
+
+    fn composite(&self, line: &Line<'a>) -> Result<CompositeRef<'a>, AfmError> {
+        parse_entry(line, &self.composite, Section::Composites, COMPOSITE_KEYS)
+    }
+}
+
+/// Receives what the section driver reads as soon as it is read.
+/// `FontMetricsRef` collects it, `Builder` builds `FontMetrics` from it,
+/// while `AfmVisitor`s see it one by one.
+///
+/// Entries come with the ids `Font::glyph` gives the glyphs they name, in
+/// order: the successor and ligature of each ligature, the two glyphs of
+/// a kern pair and the glyph of each composite part.
+trait Sink<'a> {
+    /// A key outside of the char metrics, kern data and composites, with
+    /// the rest of its line.
+    fn header_key(&mut self, key: &[u8], value: &[u8]);
+
+    fn char_metric(&mut self, metric: CharMetricRef<'a>, glyphs: &[GlyphId]);
+
+    fn kern_pair(&mut self, set: KernPairSet, pair: KernPairRef<'a>, glyphs: &[GlyphId]);
+
+    fn track_kern(&mut self, track: TrackKern);
+
+    fn composite(&mut self, composite: CompositeRef<'a>, glyphs: &[GlyphId]);
+}
+
+/// The font metrics of a file, or of a descendant of a composite font,
+/// while their lines are read.
+struct Font<'a, S> {
+    /// The values outside of the char metrics, kern data and composites.
+    header: FontMetrics,
+    /// The glyph names read so far, each interned once with its id: the
+    /// index of the first char metrics with the name, or until those are
+    /// read, the unknown glyph `forward` holds it as.
+    glyphs: HashMap<Cow<'a, str>, GlyphId>,
+    /// The names used by ligatures, kern pairs and composites before their
+    /// char metrics were read, with the id of the char metrics read later,
+    /// if ever.
+    forward: Vec<(String, Option<GlyphId>)>,
+    /// The uses of names in `forward`, with their section and position.
+    unresolved: Vec<(GlyphId, Section, Position)>,
+    /// The ids of the glyphs the entry being read names.
+    ids: Vec<GlyphId>,
+    /// The first glyph of the last kern pair with char metrics, which the
+    /// kern pairs after it mostly share.
+    first: Option<(Cow<'a, str>, GlyphId)>,
+    /// The number of char metrics and kern pairs read so far, checked
+    /// against the limits.
+    char_metrics: usize,
+    kern_pairs: usize,
+    sink: S,
+}
+
+impl<'a, S: Sink<'a>> Font<'a, S> {
+    fn new(version: AfmVersion, sink: S) -> Font<'a, S> {
+        Font {
+            header: FontMetrics {
+                afm_version: version,
+                ..FontMetrics::default()
             },
-        )
+            glyphs: HashMap::new(),
+            forward: Vec::new(),
+            unresolved: Vec::new(),
+            ids: Vec::new(),
+            first: None,
+            char_metrics: 0,
+            kern_pairs: 0,
+            sink,
+        }
+    }
+
+    /// Passes the header line `line` on to the sink, and records its span.
+    fn header_line(&mut self, line: &Line) {
+        let (column, keyword) = line.token(0);
+        let key = String::from_utf8_lossy(keyword).into_owned();
+        self.header.header_spans.push((key, line.span()));
+        let value = &line.text[column + keyword.len()..];
+        let start = value.iter().take_while(|&&c| char_class::space(c)).count();
+        let len = value.iter().rposition(|&c| !char_class::space(c)).map_or(0, |i| i + 1);
+        self.sink.header_key(keyword, &value[start.min(len)..len]);
+    }
+
+    /// Interns `name` as the name of the char metrics read next, unless
+    /// earlier ones have it. Returns their id.
+    fn define_glyph(&mut self, name: Cow<'a, str>) -> GlyphId {
+        let id = GlyphId(self.char_metrics as u32);
+        match self.glyphs.entry(name) {
+            Entry::Occupied(mut entry) => {
+                if let Some(i) = entry.get().unknown_index() {
+                    self.forward[i].1 = Some(id);
+                    entry.insert(id);
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(id);
+            }
+        }
+        id
+    }
+
+    /// The id of the glyph `name`, used at `line`. Names without char
+    /// metrics so far are noted, to be reported unless the font has char
+    /// metrics for them. Those names are few, and copied.
+    fn glyph(&mut self, name: &str, line: &Line, section: Section) -> GlyphId {
+        let id = match self.glyphs.get(name) {
+            Some(&id) => id,
+            None => {
+                let id = GlyphId::unknown(self.forward.len());
+                self.forward.push((name.to_string(), None));
+                self.glyphs.insert(Cow::Owned(name.to_string()), id);
+                id
+            }
+        };
+        if let Some(i) = id.unknown_index() {
+            if self.forward[i].1.is_none() {
+                let position = line.position(line.token(0).0);
+                self.unresolved.push((id, section, position));
+            }
+        }
+        id
+    }
+
+    /// Fills `ids` with the ids of the glyphs `names`, see `glyph`.
+    fn use_glyphs<'n, I>(&mut self, names: I, line: &Line, section: Section)
+    where
+        I: IntoIterator<Item = &'n Cow<'a, str>>,
+        'a: 'n,
+    {
+        let mut ids = mem::take(&mut self.ids);
+        ids.clear();
+        ids.extend(names.into_iter().map(|name| self.glyph(name, line, section)));
+        self.ids = ids;
+    }
+
+    /// The ids of the glyphs of a kern pair, see `glyph`.
+    fn kern_pair_glyphs(&mut self, pair: &KernPairRef<'a>, line: &Line) -> [GlyphId; 2] {
+        let first = match self.first {
+            Some((ref name, id)) if *name == pair.first_kern_character => id,
+            _ => {
+                let id = self.glyph(&pair.first_kern_character, line, Section::KernPairs);
+                if id.unknown_index().is_none() {
+                    self.first = Some((pair.first_kern_character.clone(), id));
+                }
+                id
+            }
+        };
+        let second = self.glyph(&pair.second_kern_character, line, Section::KernPairs);
+        [first, second]
+    }
+
+    /// The char metrics an id from `glyph` refers to once the whole font
+    /// is read, `None` for a glyph without any.
+    fn resolved(&self, id: GlyphId) -> Option<GlyphId> {
+        match id.unknown_index() {
+            Some(i) => self.forward[i].1,
+            None => Some(id),
+        }
+    }
+
+    /// Reports the glyphs used without char metrics in the whole font as
+    /// `UnknownGlyph` warnings, in strict mode too. Their entries are kept,
+    /// naming glyphs of `FontMetrics::unknown_glyphs`.
+    fn report_unknown_glyphs(&mut self, lines: &mut Lines) {
+        for (id, section, position) in mem::take(&mut self.unresolved) {
+            if self.resolved(id).is_none() {
+                let i = id.unknown_index().unwrap_or_default();
+                lines.warn(AfmError::UnknownGlyph {
+                    name: self.forward[i].0.clone(),
+                    section,
+                    position,
+                });
+            }
+        }
+    }
+}
+
+/// Reads the char metrics. Ligatures naming glyphs without char metrics are
+/// reported once the font has been read.
+fn char_metrics<'a, G: Grammar<'a>, S: Sink<'a>>(
+    lines: &mut Lines<'a>,
+    start: &Line,
+    grammar: &G,
+    font: &mut Font<'a, S>,
+) -> Result<(), AfmError> {
+    visit_section(lines, start, Section::CharMetrics, |lines, line| {
+        let mut metric = grammar.char_metric(lines, line)?;
+        metric.span = Some(line.span());
+        lines.check_limit(Limit::Glyphs, font.char_metrics + 1, line, Section::CharMetrics)?;
+        font.define_glyph(metric.name.clone());
+        font.char_metrics += 1;
+        let ligatures = metric.ligatures.iter();
+        let names = ligatures.flat_map(|ligature| [&ligature.successor, &ligature.ligature]);
+        font.use_glyphs(names, line, Section::CharMetrics);
+        font.sink.char_metric(metric, &font.ids);
+        Ok(())
+    })
+}
+
+/// Reads the lines of a `StartKernData` block.
+fn kern_data<'a, G: Grammar<'a>, S: Sink<'a>>(
+    lines: &mut Lines<'a>,
+    grammar: &G,
+    font: &mut Font<'a, S>,
+) -> Result<(), AfmError> {
+    block(lines, Section::KernData, |lines, line| {
+        let (column, keyword) = line.token(0);
+        let set = match keyword {
+            b"StartKernPairs" => KernPairSet::Pairs,
+            b"StartKernPairs0" => KernPairSet::Pairs0,
+            b"StartKernPairs1" => KernPairSet::Pairs1,
+            b"StartTrackKern" => {
+                let parser = track_kern();
+                return visit_section(lines, line, Section::TrackKern, |_, line| {
+                    let track = parse_entry(line, &parser, Section::TrackKern, TRACK_KERN_KEYS)?;
+                    font.sink.track_kern(track);
+                    Ok(())
+                });
+            }
+            _ => {
+                return Err(unexpected_keyword(
+                    keyword,
+                    Section::KernData,
+                    line.position(column),
+                ))
+            }
+        };
+        visit_section(lines, line, Section::KernPairs, |lines, line| {
+            let mut pair = grammar.kern_pair(line)?;
+            pair.span = Some(line.span());
+            font.kern_pairs += 1;
+            lines.check_limit(Limit::KernPairs, font.kern_pairs, line, Section::KernPairs)?;
+            let glyphs = font.kern_pair_glyphs(&pair, line);
+            font.sink.kern_pair(set, pair, &glyphs);
+            Ok(())
+        })
+    })
+}
+
+/// Parses the font metrics command at `line`, including the lines of the
+/// section it starts. Unknown keywords are reported as part of `section`.
+///
+/// The names kern pairs and composites give glyphs are noted for
+/// `Font::report_unknown_glyphs`.
+fn font_command<'a, G: Grammar<'a>, S: Sink<'a>>(
+    lines: &mut Lines<'a>,
+    line: &Line<'a>,
+    section: Section,
+    font: &mut Font<'a, S>,
+    grammar: &G,
+) -> Result<(), AfmError> {
+    match line.token(0).1 {
+        b"StartCharMetrics" => char_metrics(lines, line, grammar, font)?,
+        b"StartComposites" => visit_section(lines, line, Section::Composites, |lines, line| {
+            let mut composite = grammar.composite(line)?;
+            composite.span = Some(line.span());
+            let parts = composite.parts.len();
+            lines.check_limit(Limit::CompositeParts, parts, line, Section::Composites)?;
+            let names = composite.parts.iter().map(|part| &part.name);
+            font.use_glyphs(names, line, Section::Composites);
+            font.sink.composite(composite, &font.ids);
+            Ok(())
+        })?,
+        b"StartKernData" => kern_data(lines, grammar, font)?,
+        b"StartDirection" => {
+            let metric_sets = font.header.metric_sets;
+            font.header_line(line);
+            let command = direction(lines, line, metric_sets, |line| {
+                let cmd = grammar.direction_entry(line)?;
+                font.header_line(line);
+                Ok(cmd)
+            })?;
+            font.sink.header_key(b"EndDirection", b"");
+            font.header.apply(command)
+        }
+        _ => {
+            let command = grammar.header_command(lines, line, section)?;
+            font.header_line(line);
+            font.header.apply(command)
+        }
+    }
+    Ok(())
+}
+
+fn font_metrics<'a, G: Grammar<'a>, S: Sink<'a>>(
+    lines: &mut Lines<'a>,
+    grammar: &G,
+    sink: S,
+) -> Result<Font<'a, S>, AfmError> {
+    let version = start_line(lines, Section::FontMetrics)?;
+    let mut font = Font::new(version, sink);
+    block(lines, Section::FontMetrics, |lines, line| {
+        font_command(lines, line, Section::FontMetrics, &mut font, grammar)
+    })?;
+    end_of_input(lines, Section::FontMetrics)?;
+    font.report_unknown_glyphs(lines);
+    Ok(font)
+}
+
+// Public functions
+
+/// Decodes the bytes of a string value, such as a `Notice`, a `Comment` or
+/// the value of an extra key.
+///
+/// AFM files do not declare a charset. Values that are valid UTF-8 are
+/// read as UTF-8, anything else as Latin-1 (ISO 8859-1), the charset of
+/// most older fonts, so that every byte stands for a character. The text
+/// of the other charsets in use, such as Mac Roman, comes out wrong where
+/// it leaves ASCII; `AfmVisitor::header_value` passes on the raw bytes for
+/// those.
+///
+/// ```
+/// # use afm::decode_text;
+/// assert_eq!(decode_text(b"\xA9 1985 Adobe"), "© 1985 Adobe");
+/// assert_eq!(decode_text("© 1985 Adobe".as_bytes()), "© 1985 Adobe");
+/// ```
+pub fn decode_text(bytes: &[u8]) -> Cow<'_, str> {
+    match str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => Cow::Owned(bytes.iter().map(|&c| char::from(c)).collect()),
+    }
+}
+
+/// Options controlling how strictly input is parsed.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct ParseOptions {
+    /// Fail on the first problem. Otherwise misplaced keywords are kept in
+    /// `extra_keys`, while malformed values, wrong counts and missing end
+    /// keywords are skipped and returned as warnings. Keys the format does
+    /// not define go to `extra_keys` in either mode. Glyphs without char metrics are never fatal: their names
+    /// go to `FontMetrics::unknown_glyphs` and are returned as warnings in
+    /// either mode. Defaults to `true`.
+    pub strict: bool,
+    /// Bounds on the input. Defaults to `ParseLimits::default()`.
+    pub limits: ParseLimits,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            strict: true,
+            limits: ParseLimits::default(),
+        }
+    }
+}
+
+/// Bounds on the input, so that untrusted files cannot make the parser
+/// spend unbounded memory. Exceeding one fails with
+/// `AfmError::LimitExceeded`, also in lenient mode.
+///
+/// The defaults are well above the needs of real fonts.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct ParseLimits {
+    /// The size of the input. Defaults to 64 MiB.
+    pub max_bytes: usize,
+    /// The length of a line without its line break. Defaults to 64 KiB.
+    pub max_line_length: usize,
+    /// The number of char metrics of a font, or of each descendant of a
+    /// composite font. Defaults to 65536.
+    pub max_glyphs: usize,
+    /// The number of kern pairs of a font, counting `StartKernPairs`,
+    /// `StartKernPairs0` and `StartKernPairs1` together. Defaults to
+    /// 1000000.
+    pub max_kern_pairs: usize,
+    /// The number of parts of a composite. Defaults to 64.
+    pub max_composite_parts: usize,
+}
+
+impl ParseLimits {
+    /// No limits at all, for trusted input.
+    pub fn unlimited() -> ParseLimits {
+        ParseLimits {
+            max_bytes: usize::MAX,
+            max_line_length: usize::MAX,
+            max_glyphs: usize::MAX,
+            max_kern_pairs: usize::MAX,
+            max_composite_parts: usize::MAX,
+        }
+    }
+
+    fn max(&self, limit: Limit) -> usize {
+        match limit {
+            Limit::Bytes => self.max_bytes,
+            Limit::LineLength => self.max_line_length,
+            Limit::Glyphs => self.max_glyphs,
+            Limit::KernPairs => self.max_kern_pairs,
+            Limit::CompositeParts => self.max_composite_parts,
+        }
+    }
+}
+
+impl Default for ParseLimits {
+    fn default() -> ParseLimits {
+        ParseLimits {
+            max_bytes: 64 << 20,
+            max_line_length: 64 << 10,
+            max_glyphs: 65536,
+            max_kern_pairs: 1_000_000,
+            max_composite_parts: 64,
+        }
+    }
+}
+
+impl FontMetrics {
+    /// Parses the content of an AFM file.
+    pub fn parse(input: &[u8]) -> Result<FontMetrics, AfmError> {
+        FontMetrics::parse_with(input, &ParseOptions::default()).map(|(metrics, _)| metrics)
+    }
+
+    /// Parses the content of an AFM file, also returning the problems
+    /// skipped in lenient mode.
+    pub fn parse_with(
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(FontMetrics, Vec<Warning>), AfmError> {
+        let lines = &mut Lines::new(input, options);
+        let font = font_metrics(lines, &HandWritten, Builder::default())?;
+        Ok((font.into_metrics(), lines.warnings.split_off(0)))
+    }
+
+    /// The line of the first header key `key`, such as `FontBBox`, if the
+    /// metrics were parsed.
+    pub fn header_span(&self, key: &str) -> Option<Span> {
+        let mut spans = self.header_spans.iter();
+        spans.find(|(name, _)| name == key).map(|&(_, span)| span)
+    }
+
+    /// Derives the values older versions of the format do not define from
+    /// the glyphs they describe. Before 3.0 there is no `CapHeight`,
+    /// `XHeight`, `Ascender` or `Descender`.
+    fn fill_version_defaults(&mut self) {
+        if self.afm_version >= AfmVersion::new(3, 0) {
+            return;
+        }
+        let top = |metrics: &FontMetrics, name| metrics.glyph_by_name(name).map(|m| m.bbox.ury);
+        let bottom = |metrics: &FontMetrics, name| metrics.glyph_by_name(name).map(|m| m.bbox.lly);
+        if self.cap_height == 0.0 {
+            self.cap_height = top(self, "H").unwrap_or(0.0);
+        }
+        if self.x_height == 0.0 {
+            self.x_height = top(self, "x").unwrap_or(0.0);
+        }
+        if self.ascender == 0.0 {
+            self.ascender = top(self, "d").unwrap_or(0.0);
+        }
+        if self.descender == 0.0 {
+            self.descender = bottom(self, "p").unwrap_or(0.0);
+        }
+    }
+
+    /// Adds default metrics for the writing directions `metric_sets` declares.
+    fn fill_directions(&mut self) {
+        if self.metric_sets != 1 && self.direction0.is_none() {
+            self.direction0 = Some(DirectionMetrics::default());
+        }
+        if (self.metric_sets == 1 || self.metric_sets == 2) && self.direction1.is_none() {
+            self.direction1 = Some(DirectionMetrics::default());
+        }
+    }
+
+    fn apply(&mut self, command: Command) {
+        match command {
+            Command::MetricsSet(metric_sets) => self.metric_sets = metric_sets,
+            Command::FontName(name) => self.font_name = name,
+            Command::FullName(name) => self.full_name = name,
+            Command::FamilyName(name) => self.family_name = name,
+            Command::Weight(weight) => self.weight = weight,
+            Command::FontBBox(bbox) => self.font_bbox = bbox,
+            Command::Version(version) => self.font_version = version,
+            Command::Notice(notice) => self.notice = notice,
+            Command::EncodingScheme(scheme) => self.encoding_scheme = scheme,
+            Command::MappingScheme(scheme) => self.mapping_scheme = scheme,
+            Command::EscChar(c) => self.esc_char = c,
+            Command::CharacterSet(charset) => self.character_set = charset,
+            Command::Characters(c) => self.characters = c,
+            Command::IsBaseFont(base_font) => self.is_base_font = base_font,
+            Command::VVector(v1, v2) => self.v_vector = (v1, v2),
+            Command::IsFixedV(fixed) => self.is_fixed_v = fixed,
+            Command::CapHeight(height) => self.cap_height = height,
+            Command::XHeight(height) => self.x_height = height,
+            Command::Ascender(asc) => self.ascender = asc,
+            Command::Descender(desc) => self.descender = desc,
+            Command::StdHW(stdhw) => self.standard_horizontal_width = stdhw,
+            Command::StdVW(stdvw) => self.standard_vertical_width = stdvw,
+            Command::Comment(comment) => self.comments.push(comment),
+            Command::Extra(key, value) => self.extra_keys.push((key, value)),
+            Command::Direction(direction, cmds) => for cmd in cmds {
+                if direction != 1 {
+                    self.direction0.get_or_insert_with(Default::default).apply(cmd);
+                }
+                if direction != 0 {
+                    self.direction1.get_or_insert_with(Default::default).apply(cmd);
+                }
+            },
+        }
+    }
+}
+
+/// A pom parser for a complete AFM file, built from a pom parser for every
+/// line.
+///
+/// It returns the same metrics as `FontMetrics::parse`, which uses a much
+/// faster hand-written parser instead; see `benches/parse.rs`.
+pub fn afm() -> Parser<u8, FontMetrics> {
+    Parser::new(|input: &mut dyn Input<u8>| {
+        let start = input.position();
+        let mut data = Vec::new();
+        while let Some(c) = input.current() {
+            data.push(c);
+            input.advance();
+        }
+        let lines = &mut Lines::new(&data, &ParseOptions::default());
+        let metrics = font_metrics(lines, &Pom::new(), Builder::default());
+        let metrics = metrics.map(|font| font.into_metrics());
+        metrics.map_err(|err| {
+            input.jump_to(start);
+            pom::Error::Custom {
+                message: err.to_string(),
+                position: start + err.position().offset,
+                inner: None,
+            }
+        })
     })
 }
 
@@ -742,11 +2144,16 @@ EndFontMetrics
         assert_eq!(r.comments[0], "UniqueID 43050");
         assert_eq!(r.comments.len(), 4);
         assert_eq!(r.weight, "Medium");
-        assert_eq!(r.italic_angle, 0.0);
-        assert_eq!(r.is_fixed_pitch, true);
+        let direction = r.direction0.as_ref().unwrap();
+        assert_eq!(direction.italic_angle, 0.0);
+        assert!(direction.is_fixed_pitch);
         assert_eq!(r.character_set, "ExtendedRoman");
-        assert_eq!(r.underline_position, -100.0);
-        assert_eq!(r.underline_thickness, 50.0);
+        assert_eq!(r.font_bbox.lower_left(), (-23.0, -250.0));
+        assert_eq!(r.font_bbox.upper_right(), (715.0, 805.0));
+        assert_eq!(r.char_metrics[1].bbox, Rect::new(236.0, -15.0, 364.0, 572.0));
+        assert_eq!(direction.underline_position, -100.0);
+        assert_eq!(direction.underline_thickness, 50.0);
+        assert_eq!(r.direction1, None);
         assert_eq!(r.font_version, "003.000");
         assert_eq!(r.notice.len(), 98);
         assert_eq!(r.cap_height, 562.0);
@@ -759,6 +2166,510 @@ EndFontMetrics
         assert_eq!(r.kern_pairs.len(), 3);
     }
 
+    #[test]
+    fn parse_direction_metrics() {
+        let input = b"StartFontMetrics 4.1
+MetricsSets 2
+StartDirection 0
+ItalicAngle -12
+IsFixedPitch false
+EndDirection
+StartDirection 1
+CharWidth 0 -1000
+EndDirection
+StartDirection 2
+UnderlineThickness 50
+EndDirection
+EndFontMetrics
+";
+        let r = FontMetrics::parse(input).unwrap();
+        assert_eq!(r.metric_sets, 2);
+        assert_eq!(
+            r.direction0,
+            Some(DirectionMetrics {
+                underline_position: 0.0,
+                underline_thickness: 50.0,
+                italic_angle: -12.0,
+                char_width: (0.0, 0.0),
+                is_fixed_pitch: false,
+            })
+        );
+        let direction1 = r.direction1.unwrap();
+        assert_eq!(direction1.char_width, (0.0, -1000.0));
+        assert_eq!(direction1.underline_thickness, 50.0);
+        assert_eq!(direction1.italic_angle, 0.0);
+
+        let input = b"StartFontMetrics 4.1
+MetricsSets 1
+StartDirection 1
+EndDirection
+EndFontMetrics
+";
+        let r = FontMetrics::parse(input).unwrap();
+        assert_eq!(r.direction0, None);
+        assert_eq!(r.direction1, Some(DirectionMetrics::default()));
+
+        let input = b"StartFontMetrics 4.1
+StartDirection 1
+ItalicAngle 0
+EndDirection
+EndFontMetrics
+";
+        assert_eq!(
+            FontMetrics::parse(input),
+            Err(AfmError::InvalidValue {
+                keyword: "StartDirection".to_string(),
+                section: Section::FontMetrics,
+                position: Position {
+                    line: 2,
+                    column: 16,
+                    offset: 36,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn report_error_positions() {
+        let input = b"StartFontMetrics 4.1\nFontName Courier\nKPX A V 12\nEndFontMetrics\n";
+        assert_eq!(
+            FontMetrics::parse(input),
+            Err(AfmError::UnexpectedKeyword {
+                keyword: "KPX".to_string(),
+                section: Section::FontMetrics,
+                position: Position {
+                    line: 3,
+                    column: 1,
+                    offset: 38,
+                },
+            })
+        );
+
+        let input = b"StartFontMetrics 4.1\nCapHeight high\nEndFontMetrics\n";
+        let err = FontMetrics::parse(input).unwrap_err();
+        assert_eq!(
+            err,
+            AfmError::InvalidValue {
+                keyword: "CapHeight".to_string(),
+                section: Section::FontMetrics,
+                position: Position {
+                    line: 2,
+                    column: 11,
+                    offset: 31,
+                },
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "2:11: invalid value for `CapHeight` in FontMetrics"
+        );
+
+        let input = b"StartFontMetrics 4.1
+StartCharMetrics 1
+C 32 ; WX six ; N space ;
+EndCharMetrics
+EndFontMetrics
+";
+        let err = FontMetrics::parse(input).unwrap_err();
+        assert_eq!(
+            err,
+            AfmError::InvalidValue {
+                keyword: "WX".to_string(),
+                section: Section::CharMetrics,
+                position: Position {
+                    line: 3,
+                    column: 8,
+                    offset: 47,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn report_section_errors() {
+        let input = b"StartFontMetrics 4.1
+StartKernData
+StartKernPairs 2
+KPX A V -80
+EndKernPairs
+EndKernData
+EndFontMetrics
+";
+        let err = FontMetrics::parse(input).unwrap_err();
+        assert_eq!(
+            err,
+            AfmError::CountMismatch {
+                section: Section::KernPairs,
+                declared: 2,
+                found: 1,
+                position: Position {
+                    line: 3,
+                    column: 1,
+                    offset: 35,
+                },
+            }
+        );
+
+        let input = b"StartFontMetrics 4.1\nFontName Courier\n";
+        let err = FontMetrics::parse(input).unwrap_err();
+        assert_eq!(
+            err,
+            AfmError::MissingEnd {
+                section: Section::FontMetrics,
+                position: Position {
+                    line: 3,
+                    column: 1,
+                    offset: 38,
+                },
+            }
+        );
+        assert_eq!(err.to_string(), "3:1: missing EndFontMetrics");
+
+        let input = b"StartFontMetrics 4.1\nStartComposites 0\nEndFontMetrics\n";
+        let err = FontMetrics::parse(input).unwrap_err();
+        assert_eq!(err.section(), Section::Composites);
+        assert_eq!(err.position().line, 3);
+    }
+
+    #[test]
+    fn parse_afm_version() {
+        let metrics = FontMetrics::parse(b"StartFontMetrics 10.12\nEndFontMetrics\n").unwrap();
+        assert_eq!(metrics.afm_version, AfmVersion::new(10, 12));
+        assert_eq!(metrics.afm_version.to_string(), "10.12");
+
+        let input = b"StartFontMetrics 2.0
+FontName Old
+StartCharMetrics 3
+C 72 ; WX 722 ; N H ; B 19 0 703 662 ;
+C 100 ; WX 500 ; N d ; B 27 -10 500 683 ;
+C 112 ; WX 500 ; N p ; B 5 -217 470 460 ;
+EndCharMetrics
+EndFontMetrics
+";
+        let metrics = FontMetrics::parse(input).unwrap();
+        assert_eq!(metrics.afm_version, AfmVersion::new(2, 0));
+        assert!(metrics.afm_version < AfmVersion::new(3, 0));
+        assert_eq!(metrics.cap_height, 662.0);
+        assert_eq!(metrics.x_height, 0.0);
+        assert_eq!(metrics.ascender, 683.0);
+        assert_eq!(metrics.descender, -217.0);
+    }
+
+    #[test]
+    fn parse_leniently() {
+        let input = b"StartFontMetrics 4.1
+FontName Courier
+XHeight abc
+VendorKey 42
+StartCharMetrics 3
+C 32 ; WX 600 ; N space ; Q 1 ;
+C 33 ; WX 600 ; N exclam ;
+EndCharMetrics
+StartKernData
+StartKernPairs 1
+KPX A V -80
+EndKernData
+EndFontMetrics
+";
+        assert!(FontMetrics::parse(input).is_err());
+
+        // Keys the format does not define are kept in strict mode as well.
+        let strict = b"StartFontMetrics 4.1
+VendorKey 42
+StartCharMetrics 1
+C 32 ; WX 600 ; N space ; Q 1 ;
+EndCharMetrics
+EndFontMetrics
+";
+        let metrics = FontMetrics::parse(strict).unwrap();
+        assert_eq!(metrics.extra_keys[0].0, "VendorKey");
+        assert_eq!(metrics.char_metrics[0].extra_keys[0].0, "Q");
+
+        let options = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        let (metrics, warnings) = FontMetrics::parse_with(input, &options).unwrap();
+        assert_eq!(metrics.font_name, "Courier");
+        assert_eq!(metrics.char_metrics.len(), 2);
+        assert_eq!(metrics.char_metrics[0].name, "space");
+        assert_eq!(metrics.kern_pairs.len(), 1);
+        assert_eq!(metrics.unknown_glyphs, vec!["A", "V"]);
+        assert_eq!(
+            metrics.extra_keys,
+            vec![("VendorKey".to_string(), "42".to_string())]
+        );
+        assert_eq!(
+            metrics.char_metrics[0].extra_keys,
+            vec![("Q".to_string(), "1".to_string())]
+        );
+
+        let warnings: Vec<_> = warnings
+            .iter()
+            .map(|warning| (warning.position().line, warning.section()))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (3, Section::FontMetrics),
+                (5, Section::CharMetrics),
+                (12, Section::KernPairs),
+                (11, Section::KernPairs),
+                (11, Section::KernPairs),
+            ]
+        );
+    }
+
+    #[test]
+    fn count_skipped_entries() {
+        let input = b"StartFontMetrics 4.1
+StartCharMetrics 2
+C 65 ; WX 722 ; N A ;
+C 86 ; WX 722 ; N V ;
+EndCharMetrics
+StartKernData
+StartKernPairs 2
+KPX A V -80
+KPX A V abc
+EndKernPairs
+EndKernData
+EndFontMetrics
+";
+        let options = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        let (metrics, warnings) = FontMetrics::parse_with(input, &options).unwrap();
+        assert_eq!(metrics.kern_pairs.len(), 1);
+        // The skipped pair is reported once, without a wrong count.
+        let warnings: Vec<_> = warnings.iter().map(|warning| warning.position().line).collect();
+        assert_eq!(warnings, vec![9]);
+    }
+
+    #[test]
+    fn report_unknown_glyphs() {
+        let input = b"StartFontMetrics 4.1
+StartCharMetrics 2
+C 102 ; WX 333 ; N f ; L f ff ;
+C 105 ; WX 278 ; N i ;
+EndCharMetrics
+StartKernData
+StartKernPairs 2
+KPX f i -20
+KPX f Aring 10
+EndKernPairs
+EndKernData
+StartComposites 1
+CC fi 2 ; PCC f 0 0 ; PCC dotlessi 300 0 ;
+EndComposites
+EndFontMetrics
+";
+        let (metrics, warnings) = FontMetrics::parse_with(input, &ParseOptions::default()).unwrap();
+        assert_eq!(metrics.unknown_glyphs, vec!["ff", "Aring", "dotlessi"]);
+        let f = &metrics.char_metrics[0];
+        assert_eq!(
+            f.ligatures,
+            vec![Ligature {
+                successor: GlyphId(0),
+                ligature: GlyphId::unknown(0),
+            }]
+        );
+        let pairs: Vec<_> = metrics
+            .kern_pairs
+            .iter()
+            .map(|pair| metrics.glyph_name(pair.second_kern_character).unwrap())
+            .collect();
+        assert_eq!(pairs, vec!["i", "Aring"]);
+        let parts = &metrics.composites[0].parts;
+        assert_eq!(parts[1].glyph, GlyphId::unknown(2));
+        assert_eq!(metrics.glyph(parts[1].glyph), None);
+        assert_eq!(metrics.glyph_name(parts[1].glyph), Some("dotlessi"));
+        assert_eq!(afm().parse(&mut DataInput::new(input)), Ok(metrics.clone()));
+
+        let warnings: Vec<_> = warnings.iter().map(|warning| warning.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "3:1: unknown glyph `ff` in CharMetrics",
+                "9:1: unknown glyph `Aring` in KernPairs",
+                "13:1: unknown glyph `dotlessi` in Composites",
+            ]
+        );
+        let options = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        let lenient = FontMetrics::parse_with(input, &options).unwrap();
+        assert_eq!(lenient.0, metrics);
+        assert_eq!(lenient.1.len(), 3);
+
+        // Char metrics following the kern pairs naming them are no
+        // unknown glyphs.
+        let input = b"StartFontMetrics 4.1
+StartKernData
+StartKernPairs 1
+KPX f i -20
+EndKernPairs
+EndKernData
+StartCharMetrics 2
+C 102 ; WX 333 ; N f ;
+C 105 ; WX 278 ; N i ;
+EndCharMetrics
+EndFontMetrics
+";
+        let (metrics, warnings) = FontMetrics::parse_with(input, &ParseOptions::default()).unwrap();
+        assert_eq!(metrics.kern_pairs[0].second_kern_character, GlyphId(1));
+        assert_eq!(metrics.unknown_glyphs, Vec::<String>::new());
+        assert_eq!(warnings, vec![]);
+    }
+
+    #[test]
+    fn parse_legacy_text() {
+        let input = b"\xEF\xBB\xBFStartFontMetrics 4.1\r\
+Comment\tMade with \xA9 and\ttabs\r\
+FullName Caf\xC3\xA9 Sans\r\
+Notice Copyright \xA9 1985 Adobe\r\
+StartCharMetrics\t1\r\
+C 32 ;\tWX 250 ; N space ;\r\
+EndCharMetrics\r\
+EndFontMetrics\r";
+        let metrics = FontMetrics::parse(input).unwrap();
+        assert_eq!(metrics.comments, vec!["Made with \u{A9} and\ttabs"]);
+        assert_eq!(metrics.full_name, "Caf\u{E9} Sans");
+        assert_eq!(metrics.notice, "Copyright \u{A9} 1985 Adobe");
+        assert_eq!(metrics.char_metrics.len(), 1);
+        assert_eq!(afm().parse(&mut DataInput::new(input)), Ok(metrics.clone()));
+        let read = FontMetrics::from_reader(&input[..]).unwrap();
+        assert_eq!(read, metrics);
+
+        // The byte order mark is left out of the columns of the first line.
+        let err = FontMetrics::parse(b"\xEF\xBB\xBFStartFontMetrics x\r").unwrap_err();
+        assert_eq!(
+            err.position(),
+            Position {
+                line: 1,
+                column: 18,
+                offset: 20,
+            }
+        );
+    }
+
+    #[test]
+    fn record_spans() {
+        let input = b"StartFontMetrics 4.1\r\n\
+FontName Test\r\n\
+StartCharMetrics 2\r\n\
+C 65 ; WX 667 ; N A ;\r\n\
+C 194 ; WX 333 ; N acute ;\r\n\
+EndCharMetrics\r\n\
+StartKernData\r\n\
+StartKernPairs 1\r\n\
+KPX A acute -20\r\n\
+EndKernPairs\r\n\
+EndKernData\r\n\
+StartComposites 1\r\n\
+CC Aacute 2 ; PCC A 0 0 ; PCC acute 195 212 ;\r\n\
+EndComposites\r\n\
+EndFontMetrics\r\n";
+        let metrics = FontMetrics::parse(input).unwrap();
+        let span = |line, start, end| Some(Span { line, start, end });
+        assert_eq!(metrics.header_span("FontName"), span(2, 22, 35));
+        assert_eq!(metrics.header_span("Notice"), None);
+        assert_eq!(metrics.char_metrics[1].span, span(5, 80, 106));
+        assert_eq!(metrics.kern_pairs[0].span, span(9, 157, 172));
+        let composite = metrics.composites[0].span.unwrap();
+        assert_eq!(composite.line, 13);
+        let text = &b"CC Aacute 2 ; PCC A 0 0 ; PCC acute 195 212 ;"[..];
+        assert_eq!(&input[composite.range()], text);
+        let read = FontMetrics::from_reader(&input[..]).unwrap();
+        assert_eq!(read.kern_pairs[0].span, metrics.kern_pairs[0].span);
+
+        // Spans are left out when comparing metrics.
+        let mut moved = metrics.clone();
+        moved.kern_pairs.edit()[0].span = None;
+        moved.header_spans.clear();
+        assert_eq!(moved, metrics);
+    }
+
+    #[test]
+    fn enforce_limits() {
+        let input = b"StartFontMetrics 4.1
+StartCharMetrics 3
+C 65 ; WX 667 ; N A ;
+C 193 ; WX 667 ; N Aacute ;
+C 194 ; WX 333 ; N acute ;
+EndCharMetrics
+StartKernData
+StartKernPairs 2
+KPX A A -10
+KPX A acute -20
+EndKernPairs
+EndKernData
+StartComposites 1
+CC Aacute 2 ; PCC A 0 0 ; PCC acute 195 212 ;
+EndComposites
+EndFontMetrics
+";
+        let parse = |limits: ParseLimits| {
+            let options = ParseOptions {
+                strict: false,
+                limits,
+            };
+            FontMetrics::parse_with(input, &options).map(|_| ())
+        };
+        let limit = |limit| {
+            let mut limits = ParseLimits::default();
+            match limit {
+                Limit::Bytes => limits.max_bytes = 100,
+                Limit::LineLength => limits.max_line_length = 40,
+                Limit::Glyphs => limits.max_glyphs = 2,
+                Limit::KernPairs => limits.max_kern_pairs = 1,
+                Limit::CompositeParts => limits.max_composite_parts = 1,
+            }
+            parse(limits).unwrap_err().to_string()
+        };
+        assert_eq!(parse(ParseLimits::default()), Ok(()));
+        assert_eq!(parse(ParseLimits::unlimited()), Ok(()));
+        assert_eq!(
+            limit(Limit::Bytes),
+            "5:1: more than 100 bytes of input in CharMetrics"
+        );
+        assert_eq!(
+            limit(Limit::LineLength),
+            "14:1: more than 40 bytes in a line in Composites"
+        );
+        assert_eq!(
+            limit(Limit::Glyphs),
+            "5:1: more than 2 char metrics in CharMetrics"
+        );
+        assert_eq!(
+            limit(Limit::KernPairs),
+            "10:1: more than 1 kern pairs in KernPairs"
+        );
+        assert_eq!(
+            limit(Limit::CompositeParts),
+            "14:1: more than 1 parts in a composite in Composites"
+        );
+
+        let options = ParseOptions {
+            limits: ParseLimits {
+                max_line_length: 40,
+                ..ParseLimits::default()
+            },
+            ..ParseOptions::default()
+        };
+        let err = FontMetrics::from_reader_with(&input[..], &options).unwrap_err();
+        let err = err.get_ref().and_then(|err| err.downcast_ref::<AfmError>());
+        assert_eq!(
+            err.map(AfmError::position),
+            Some(Position {
+                line: 14,
+                column: 1,
+                offset: 234,
+            })
+        );
+    }
+
     #[test]
     fn parse_standard_14_pdf_fonts() {
         use std::fs;
@@ -772,7 +2683,7 @@ EndFontMetrics
                 let mut file = File::open(&path).expect("Could not open an asset file");
                 let mut v = Vec::new();
                 file.read_to_end(&mut v)
-                    .expect(&format!("Could not read {}", path.display()));
+                    .unwrap_or_else(|_| panic!("Could not read {}", path.display()));
 
                 let mut buf = DataInput::new(&v);
                 let parse_result = afm().parse(&mut buf);
@@ -0,0 +1,431 @@
+use pom::char_class;
+use pom::parser::*;
+use pom::Parser;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{
+    block, check_count, decode_text, end_of_input, header_command, integer, keyword_value,
+    line_entry, name, name_char, number, space, start_line, string, string_char, uinteger,
+    unexpected_keyword, AfmError, FontMetrics, Lines, ParseOptions, Section, Warning,
+};
+use read::read_with;
+
+/// The metrics of an Adobe Multiple Master font, read from an AMFM file.
+///
+/// The metrics of the individual masters live in separate AFM files, see
+/// `MasterFontMetrics::resolve_masters`.
+#[derive(PartialEq, Debug, Default)]
+pub struct MasterFontMetrics {
+    /// The global font information such as `FontName` or `FontBBox`.
+    pub global_info: FontMetrics,
+    pub axes: Vec<Axis>,
+    pub masters: Vec<Master>,
+    pub weight_vector: Vec<f64>,
+    pub blend_design_positions: Vec<Vec<f64>>,
+    /// Per axis, the mapping from design coordinates to normalized coordinates.
+    pub blend_design_map: Vec<Vec<(f64, f64)>>,
+    pub blend_axis_types: Vec<String>,
+    pub primary_fonts: Vec<PrimaryFont>,
+    /// The raw lines of the `StartConversionPrograms` section.
+    pub conversion_programs: Vec<String>,
+}
+
+#[derive(PartialEq, Debug, Default)]
+pub struct Axis {
+    pub axis_type: String,
+    pub axis_label: String,
+}
+
+#[derive(PartialEq, Debug, Default)]
+pub struct Master {
+    pub font_name: String,
+    pub full_name: String,
+    pub version: String,
+    pub weight_vector: Vec<f64>,
+}
+
+#[derive(PartialEq, Debug, Default)]
+pub struct PrimaryFont {
+    pub coordinates: Vec<i32>,
+    pub label: String,
+    pub name: String,
+}
+
+// Types
+
+fn array<O: 'static>(item: Parser<u8, O>) -> Parser<u8, Vec<O>> {
+    let spaces = || is_a(char_class::space).repeat(0..);
+    sym(b'[') * spaces() * list(item, spaces()) - spaces() - sym(b']')
+}
+
+fn literal_name() -> Parser<u8, String> {
+    let name_char = |c| name_char(c) && !b"[]{}()<>/%".contains(&c);
+    sym(b'/') * is_a(name_char).repeat(1..).convert(String::from_utf8)
+}
+
+fn design_map() -> Parser<u8, Vec<Vec<(f64, f64)>>> {
+    let point = array(number()).convert(|v| match v[..] {
+        [design, normalized] => Ok((design, normalized)),
+        _ => Err("expected a pair"),
+    });
+    array(array(point))
+}
+
+// Primary Fonts
+
+enum PrimaryFontCmd {
+    PC(Vec<i32>),
+    PL(String),
+    PN(String),
+}
+
+fn primary_font() -> Parser<u8, PrimaryFont> {
+    let label = is_a(|c| string_char(c) && c != b';')
+        .repeat(1..)
+        .map(|bytes| decode_text(&bytes).into_owned());
+    let command = (seq(b"PC") - space()) * list(integer(), space()).map(PrimaryFontCmd::PC)
+        | (seq(b"PL") - space()) * label.map(|l| PrimaryFontCmd::PL(unquote(&l)))
+        | (seq(b"PN") - space()) * name().map(PrimaryFontCmd::PN);
+    let seperator = || space().opt() * sym(b';') - space().opt();
+    let cmds = list(command, seperator()) - seperator().opt();
+    cmds.map(|commands| {
+        commands.into_iter().fold(
+            PrimaryFont::default(),
+            |mut font: PrimaryFont, command: PrimaryFontCmd| {
+                match command {
+                    PrimaryFontCmd::PC(coordinates) => font.coordinates = coordinates,
+                    PrimaryFontCmd::PL(label) => font.label = label,
+                    PrimaryFontCmd::PN(name) => font.name = name,
+                }
+                font
+            },
+        )
+    })
+}
+
+/// Strips the parentheses of a PostScript string like `(Light Condensed)`.
+fn unquote(label: &str) -> String {
+    let label = label.trim();
+    if label.starts_with('(') && label.ends_with(')') {
+        label[1..label.len() - 1].to_string()
+    } else {
+        label.to_string()
+    }
+}
+
+static PRIMARY_FONT_KEYS: &[&[u8]] = &[b"PC", b"PL", b"PN"];
+
+// Sections
+
+fn axis(lines: &mut Lines) -> Result<Axis, AfmError> {
+    let mut axis = Axis::default();
+    block(lines, Section::Axis, |_, line| {
+        let (column, keyword) = line.token(0);
+        match keyword {
+            b"AxisType" => axis.axis_type = keyword_value(line, name(), Section::Axis)?,
+            b"AxisLabel" => axis.axis_label = keyword_value(line, string(), Section::Axis)?,
+            _ => {
+                return Err(unexpected_keyword(
+                    keyword,
+                    Section::Axis,
+                    line.position(column),
+                ))
+            }
+        }
+        Ok(())
+    })?;
+    Ok(axis)
+}
+
+fn master(lines: &mut Lines) -> Result<Master, AfmError> {
+    let mut master = Master::default();
+    block(lines, Section::Master, |_, line| {
+        let (column, keyword) = line.token(0);
+        match keyword {
+            b"FontName" => master.font_name = keyword_value(line, name(), Section::Master)?,
+            b"FullName" => master.full_name = keyword_value(line, string(), Section::Master)?,
+            b"Version" => master.version = keyword_value(line, string(), Section::Master)?,
+            b"WeightVector" => {
+                master.weight_vector = keyword_value(line, array(number()), Section::Master)?
+            }
+            _ => {
+                return Err(unexpected_keyword(
+                    keyword,
+                    Section::Master,
+                    line.position(column),
+                ))
+            }
+        }
+        Ok(())
+    })?;
+    Ok(master)
+}
+
+fn master_font_metrics(lines: &mut Lines) -> Result<MasterFontMetrics, AfmError> {
+    let version = start_line(lines, Section::MasterFontMetrics)?;
+
+    let mut metrics = MasterFontMetrics::default();
+    metrics.global_info.afm_version = version;
+    let mut masters = None;
+    let mut axes = None;
+    block(lines, Section::MasterFontMetrics, |lines, line| {
+        let section = Section::MasterFontMetrics;
+        let position = line.position(line.token(0).0);
+        match line.token(0).1 {
+            b"Masters" => masters = Some((keyword_value(line, uinteger(), section)?, position)),
+            b"Axes" => axes = Some((keyword_value(line, uinteger(), section)?, position)),
+            b"WeightVector" => {
+                metrics.weight_vector = keyword_value(line, array(number()), section)?
+            }
+            b"BlendDesignPositions" => {
+                metrics.blend_design_positions =
+                    keyword_value(line, array(array(number())), section)?
+            }
+            b"BlendDesignMap" => {
+                metrics.blend_design_map = keyword_value(line, design_map(), section)?
+            }
+            b"BlendAxisTypes" => {
+                metrics.blend_axis_types = keyword_value(line, array(literal_name()), section)?
+            }
+            b"StartAxis" => metrics.axes.push(axis(lines)?),
+            b"StartMaster" => metrics.masters.push(master(lines)?),
+            b"StartPrimaryFonts" => {
+                let entry = line_entry(primary_font(), Section::PrimaryFonts, PRIMARY_FONT_KEYS);
+                metrics.primary_fonts = super::section(lines, line, Section::PrimaryFonts, entry)?
+            }
+            b"StartConversionPrograms" => block(lines, Section::ConversionPrograms, |_, line| {
+                let text = String::from_utf8_lossy(&line.text).trim().to_string();
+                metrics.conversion_programs.push(text);
+                Ok(())
+            })?,
+            _ => metrics
+                .global_info
+                .apply(header_command(lines, line, section)?),
+        }
+        Ok(())
+    })?;
+    end_of_input(lines, Section::MasterFontMetrics)?;
+
+    check_count(lines, masters, metrics.masters.len(), Section::Master)?;
+    check_count(lines, axes, metrics.axes.len(), Section::Axis)?;
+    Ok(metrics)
+}
+
+impl MasterFontMetrics {
+    /// Parses the content of an AMFM file.
+    pub fn parse(input: &[u8]) -> Result<MasterFontMetrics, AfmError> {
+        MasterFontMetrics::parse_with(input, &ParseOptions::default()).map(|(metrics, _)| metrics)
+    }
+
+    /// Parses the content of an AMFM file, also returning the problems
+    /// skipped in lenient mode.
+    pub fn parse_with(
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(MasterFontMetrics, Vec<Warning>), AfmError> {
+        let lines = &mut Lines::new(input, options);
+        let metrics = master_font_metrics(lines)?;
+        Ok((metrics, lines.warnings.split_off(0)))
+    }
+
+    /// Parses an AMFM file read from `reader` line by line. Parse errors
+    /// are returned as `InvalidData` wrapping the `AfmError`.
+    pub fn from_reader<R: Read>(reader: R) -> io::Result<MasterFontMetrics> {
+        MasterFontMetrics::from_reader_with(reader, &ParseOptions::default())
+            .map(|(metrics, _)| metrics)
+    }
+
+    /// Like `from_reader`, also returning the problems skipped in lenient
+    /// mode.
+    pub fn from_reader_with<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> io::Result<(MasterFontMetrics, Vec<Warning>)> {
+        read_with(reader, options, master_font_metrics)
+    }
+
+    /// Parses the AMFM file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<MasterFontMetrics> {
+        MasterFontMetrics::from_reader(File::open(path)?)
+    }
+
+    /// Parses the AFM file of every master. `read` is given the `FontName`
+    /// of a master and returns the content of its AFM file.
+    ///
+    /// ```no_run
+    /// # use afm::MasterFontMetrics;
+    /// # use std::fs;
+    /// # let amfm = MasterFontMetrics::default();
+    /// let masters = amfm.resolve_masters(|name| fs::read(format!("{}.afm", name)));
+    /// ```
+    pub fn resolve_masters<F>(&self, mut read: F) -> io::Result<Vec<FontMetrics>>
+    where
+        F: FnMut(&str) -> io::Result<Vec<u8>>,
+    {
+        self.masters
+            .iter()
+            .map(|master| {
+                let data = read(&master.font_name)?;
+                FontMetrics::parse(&data)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            })
+            .collect()
+    }
+}
+
+impl FromStr for MasterFontMetrics {
+    type Err = AfmError;
+
+    fn from_str(input: &str) -> Result<MasterFontMetrics, AfmError> {
+        MasterFontMetrics::parse(input.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Position;
+
+    static AMFM: &[u8] = br#"StartMasterFontMetrics 4.0
+Comment Copyright 1991 Adobe Systems Incorporated
+FontName MyriadMM
+FullName Myriad MM
+FamilyName Myriad MM
+Weight All
+ItalicAngle 0
+IsFixedPitch false
+FontBBox -50 -250 1245 920
+Version 001.002
+Masters 4
+Axes 2
+WeightVector [0.25 0.25 0.25 0.25]
+BlendDesignPositions [[0 0] [1 0] [0 1] [1 1]]
+BlendDesignMap [[[215 0][830 1]] [[300 0][700 1]]]
+BlendAxisTypes [/Weight /Width]
+StartAxis
+AxisType Weight
+AxisLabel Weight
+EndAxis
+StartAxis
+AxisType Width
+AxisLabel Width
+EndAxis
+StartMaster
+FontName MyriadMM-LightCn
+FullName Myriad MM Light Condensed
+Version 001.002
+WeightVector [1 0 0 0]
+EndMaster
+StartMaster
+FontName MyriadMM-BlackCn
+FullName Myriad MM Black Condensed
+Version 001.002
+WeightVector [0 1 0 0]
+EndMaster
+StartMaster
+FontName MyriadMM-LightSemiExt
+FullName Myriad MM Light SemiExtended
+Version 001.002
+WeightVector [0 0 1 0]
+EndMaster
+StartMaster
+FontName MyriadMM-BlackSemiExt
+FullName Myriad MM Black SemiExtended
+Version 001.002
+WeightVector [0 0 0 1]
+EndMaster
+StartPrimaryFonts 2
+PC 215 300 ; PL (Light Condensed) ;
+PC 830 700 ; PL (Black SemiExtended) ;
+EndPrimaryFonts
+StartConversionPrograms 1 1
+NDV { exch 215 sub 615 div exch 300 sub 400 div }
+CDV { 2 copy mul 3 1 roll }
+EndConversionPrograms
+EndMasterFontMetrics
+"#;
+
+    #[test]
+    fn parse_master_font_metrics() {
+        let amfm = MasterFontMetrics::parse(AMFM).unwrap();
+        assert_eq!(amfm.global_info.font_name, "MyriadMM");
+        assert_eq!(amfm.global_info.comments.len(), 1);
+        assert_eq!(amfm.weight_vector, vec![0.25; 4]);
+        assert_eq!(amfm.blend_design_positions[3], vec![1.0, 1.0]);
+        assert_eq!(
+            amfm.blend_design_map,
+            vec![
+                vec![(215.0, 0.0), (830.0, 1.0)],
+                vec![(300.0, 0.0), (700.0, 1.0)],
+            ]
+        );
+        assert_eq!(amfm.blend_axis_types, vec!["Weight", "Width"]);
+        assert_eq!(
+            amfm.axes[1],
+            Axis {
+                axis_type: "Width".to_string(),
+                axis_label: "Width".to_string(),
+            }
+        );
+        assert_eq!(amfm.masters.len(), 4);
+        assert_eq!(amfm.masters[1].font_name, "MyriadMM-BlackCn");
+        assert_eq!(amfm.masters[1].weight_vector, vec![0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(
+            amfm.primary_fonts[0],
+            PrimaryFont {
+                coordinates: vec![215, 300],
+                label: "Light Condensed".to_string(),
+                name: String::new(),
+            }
+        );
+        assert_eq!(amfm.conversion_programs.len(), 2);
+    }
+
+    #[test]
+    fn check_master_count() {
+        let input = b"StartMasterFontMetrics 4.0
+Masters 2
+StartMaster
+FontName MyriadMM-LightCn
+EndMaster
+EndMasterFontMetrics
+";
+        let err = MasterFontMetrics::parse(input).unwrap_err();
+        assert_eq!(
+            err,
+            AfmError::CountMismatch {
+                section: Section::Master,
+                declared: 2,
+                found: 1,
+                position: Position {
+                    line: 2,
+                    column: 1,
+                    offset: 27,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn resolve_masters() {
+        let amfm = MasterFontMetrics::parse(AMFM).unwrap();
+        let masters = amfm
+            .resolve_masters(|name| {
+                Ok(
+                    format!("StartFontMetrics 4.1\nFontName {}\nEndFontMetrics\n", name)
+                        .into_bytes(),
+                )
+            })
+            .unwrap();
+        assert_eq!(masters.len(), 4);
+        assert_eq!(masters[2].font_name, "MyriadMM-LightSemiExt");
+
+        let err = amfm
+            .resolve_masters(|_| Ok(b"EndFontMetrics\n".to_vec()))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
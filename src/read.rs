@@ -0,0 +1,160 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{font_metrics, AfmError, FontMetrics, Lines, ParseOptions, Warning};
+use borrowed::HandWritten;
+use build::Builder;
+
+/// Runs `parse` on the lines of `reader`, read one at a time. An error
+/// reading the input takes precedence over the parse error it leads to,
+/// while parse errors are returned as `InvalidData` wrapping the `AfmError`.
+pub(crate) fn read_with<R, T, F>(
+    reader: R,
+    options: &ParseOptions,
+    parse: F,
+) -> io::Result<(T, Vec<Warning>)>
+where
+    R: Read,
+    F: FnOnce(&mut Lines) -> Result<T, AfmError>,
+{
+    let mut reader = BufReader::new(reader);
+    let lines = &mut Lines::from_reader(&mut reader, options);
+    let parsed = parse(lines);
+    if let Some(err) = lines.error.take() {
+        return Err(err);
+    }
+    let parsed = parsed.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Ok((parsed, lines.warnings.split_off(0)))
+}
+
+impl FontMetrics {
+    /// Parses an AFM file read from `reader` line by line, without holding
+    /// the whole input in memory. Parse errors are returned as
+    /// `InvalidData` wrapping the `AfmError`:
+    ///
+    /// ```
+    /// # use afm::{AfmError, FontMetrics};
+    /// let err = FontMetrics::from_reader(&b"StartFontMetrics 4.1\n"[..]).unwrap_err();
+    /// let err = err.get_ref().and_then(|err| err.downcast_ref::<AfmError>());
+    /// assert_eq!(err.unwrap().to_string(), "2:1: missing EndFontMetrics");
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> io::Result<FontMetrics> {
+        FontMetrics::from_reader_with(reader, &ParseOptions::default()).map(|(metrics, _)| metrics)
+    }
+
+    /// Like `from_reader`, also returning the problems skipped in lenient
+    /// mode.
+    pub fn from_reader_with<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> io::Result<(FontMetrics, Vec<Warning>)> {
+        read_with(reader, options, |lines| {
+            let font = font_metrics(lines, &HandWritten, Builder::default())?;
+            Ok(font.into_metrics())
+        })
+    }
+
+    /// Parses the AFM file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<FontMetrics> {
+        FontMetrics::from_reader(File::open(path)?)
+    }
+}
+
+impl FromStr for FontMetrics {
+    type Err = AfmError;
+
+    fn from_str(input: &str) -> Result<FontMetrics, AfmError> {
+        FontMetrics::parse(input.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::io::BufRead;
+    use std::path::Path;
+    use {CompositeFontMetrics, Position, Section};
+
+    /// Hands out its input a few bytes at a time, splitting `\r\n` line
+    /// breaks across reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn read_line_breaks() {
+        let input = b"one\r\ntwo\rthree\n\nfour";
+        let mut reader = BufReader::with_capacity(4, Trickle(input));
+        let lines = Lines::from_reader(&mut reader, &ParseOptions::default());
+        let lines: Vec<_> = lines
+            .map(|line| (line.number, line.offset, line.text.into_owned()))
+            .collect();
+        let expected: Vec<_> = Lines::new(input, &ParseOptions::default())
+            .map(|line| (line.number, line.offset, line.text.into_owned()))
+            .collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines, expected);
+        assert!(reader.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_assets() {
+        for file in fs::read_dir(Path::new("assets")).unwrap() {
+            let path = file.unwrap().path();
+            if path.extension() != Some(OsStr::new("afm")) {
+                continue;
+            }
+            let data = fs::read(&path).unwrap();
+            let read = FontMetrics::from_reader(Trickle(&data)).unwrap();
+            assert_eq!(read, FontMetrics::parse(&data).unwrap());
+            assert_eq!(FontMetrics::from_path(&path).unwrap(), read);
+        }
+
+        let text = "StartFontMetrics 4.1\nFontName Test\nEndFontMetrics\n";
+        let metrics: FontMetrics = text.parse().unwrap();
+        assert_eq!(metrics.font_name, "Test");
+    }
+
+    #[test]
+    fn report_read_errors() {
+        let input = b"StartCompFontMetrics 4.0\r\nDescendants 2\r\nEndCompFontMetrics\r\n";
+        let err = CompositeFontMetrics::from_reader(&input[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = err.get_ref().and_then(|err| err.downcast_ref::<AfmError>());
+        assert_eq!(
+            err,
+            Some(&AfmError::CountMismatch {
+                section: Section::Descendent,
+                declared: 2,
+                found: 0,
+                position: Position {
+                    line: 2,
+                    column: 1,
+                    offset: 26,
+                },
+            })
+        );
+
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::PermissionDenied, "no access"))
+            }
+        }
+        let err = FontMetrics::from_reader(Failing).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(err.to_string(), "no access");
+        assert!(FontMetrics::from_path("assets/missing.afm").is_err());
+    }
+}
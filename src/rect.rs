@@ -0,0 +1,82 @@
+/// A rectangle given by its lower-left and upper-right corners, as used
+/// for `FontBBox` and the `B` key of char metrics.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct Rect {
+    pub llx: f64,
+    pub lly: f64,
+    pub urx: f64,
+    pub ury: f64,
+}
+
+impl Rect {
+    pub const fn new(llx: f64, lly: f64, urx: f64, ury: f64) -> Rect {
+        Rect { llx, lly, urx, ury }
+    }
+
+    pub fn lower_left(&self) -> (f64, f64) {
+        (self.llx, self.lly)
+    }
+
+    pub fn upper_right(&self) -> (f64, f64) {
+        (self.urx, self.ury)
+    }
+
+    pub fn width(&self) -> f64 {
+        self.urx - self.llx
+    }
+
+    pub fn height(&self) -> f64 {
+        self.ury - self.lly
+    }
+
+    /// Whether the rectangle has no area, like the box of a space.
+    pub fn is_empty(&self) -> bool {
+        self.width() <= 0.0 || self.height() <= 0.0
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            llx: self.llx.min(other.llx),
+            lly: self.lly.min(other.lly),
+            urx: self.urx.max(other.urx),
+            ury: self.ury.max(other.ury),
+        }
+    }
+
+    /// Whether `other` lies within this rectangle, including its edges.
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.llx <= other.llx
+            && self.lly <= other.lly
+            && other.urx <= self.urx
+            && other.ury <= self.ury
+    }
+
+    /// Whether the point lies within this rectangle, including its edges.
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        self.llx <= x && x <= self.urx && self.lly <= y && y <= self.ury
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_geometry() {
+        let a = Rect::new(-10.0, -20.0, 590.0, 700.0);
+        assert_eq!(a.lower_left(), (-10.0, -20.0));
+        assert_eq!(a.upper_right(), (590.0, 700.0));
+        assert_eq!((a.width(), a.height()), (600.0, 720.0));
+        assert!(!a.is_empty());
+        assert!(Rect::default().is_empty());
+
+        let b = Rect::new(100.0, -200.0, 800.0, 500.0);
+        let union = a.union(&b);
+        assert_eq!(union, Rect::new(-10.0, -200.0, 800.0, 700.0));
+        assert!(union.contains(&a) && union.contains(&b));
+        assert!(!a.contains(&b));
+        assert!(a.contains_point(590.0, 0.0));
+        assert!(!a.contains_point(0.0, 701.0));
+    }
+}
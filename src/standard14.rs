@@ -0,0 +1,221 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use super::{FontMetrics, StaticFontMetrics};
+
+/// The statics `afm-codegen` wrote for the AFM files in `assets/`.
+mod tables {
+    use {GlyphId, Ligature, Rect, StaticCharMetric, StaticFontMetrics, StaticKernPair};
+
+    include!("standard14_tables.rs");
+}
+
+/// The 14 fonts every PDF reader provides, whose metrics come with this
+/// crate. They are Adobe's AFM files in `assets/`, see `MustRead.html`
+/// there for their terms of use.
+///
+/// ```
+/// # use afm::Standard14;
+/// let helvetica = Standard14::from_postscript_name("Helvetica").unwrap();
+/// let metrics = helvetica.metrics();
+/// assert_eq!(metrics.glyph_by_name("A").unwrap().wx, 667.0);
+/// ```
+///
+/// The metrics also come as static tables, which need no parsing:
+///
+/// ```
+/// # use afm::{Metrics, Standard14};
+/// let metrics = Standard14::Helvetica.static_metrics();
+/// let a = metrics.glyph_id("A").unwrap();
+/// assert_eq!(metrics.width(a), Some(667.0));
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub enum Standard14 {
+    Courier,
+    CourierBold,
+    CourierOblique,
+    CourierBoldOblique,
+    Helvetica,
+    HelveticaBold,
+    HelveticaOblique,
+    HelveticaBoldOblique,
+    TimesRoman,
+    TimesBold,
+    TimesItalic,
+    TimesBoldItalic,
+    Symbol,
+    ZapfDingbats,
+}
+
+/// The metrics of each font once parsed, in the order of `Standard14::ALL`.
+static METRICS: [OnceLock<FontMetrics>; 14] = [const { OnceLock::new() }; 14];
+
+impl Standard14 {
+    /// Every font, in the order of the variants.
+    pub const ALL: [Standard14; 14] = [
+        Standard14::Courier,
+        Standard14::CourierBold,
+        Standard14::CourierOblique,
+        Standard14::CourierBoldOblique,
+        Standard14::Helvetica,
+        Standard14::HelveticaBold,
+        Standard14::HelveticaOblique,
+        Standard14::HelveticaBoldOblique,
+        Standard14::TimesRoman,
+        Standard14::TimesBold,
+        Standard14::TimesItalic,
+        Standard14::TimesBoldItalic,
+        Standard14::Symbol,
+        Standard14::ZapfDingbats,
+    ];
+
+    /// The `FontName` of the font, such as `Times-BoldItalic`, by which PDF
+    /// files refer to it.
+    pub fn postscript_name(self) -> &'static str {
+        match self {
+            Standard14::Courier => "Courier",
+            Standard14::CourierBold => "Courier-Bold",
+            Standard14::CourierOblique => "Courier-Oblique",
+            Standard14::CourierBoldOblique => "Courier-BoldOblique",
+            Standard14::Helvetica => "Helvetica",
+            Standard14::HelveticaBold => "Helvetica-Bold",
+            Standard14::HelveticaOblique => "Helvetica-Oblique",
+            Standard14::HelveticaBoldOblique => "Helvetica-BoldOblique",
+            Standard14::TimesRoman => "Times-Roman",
+            Standard14::TimesBold => "Times-Bold",
+            Standard14::TimesItalic => "Times-Italic",
+            Standard14::TimesBoldItalic => "Times-BoldItalic",
+            Standard14::Symbol => "Symbol",
+            Standard14::ZapfDingbats => "ZapfDingbats",
+        }
+    }
+
+    /// The font with the PostScript name `name`.
+    pub fn from_postscript_name(name: &str) -> Option<Standard14> {
+        let mut fonts = Standard14::ALL.iter();
+        fonts.find(|font| font.postscript_name() == name).cloned()
+    }
+
+    /// The content of the font's AFM file.
+    pub fn afm_data(self) -> &'static [u8] {
+        match self {
+            Standard14::Courier => include_bytes!("../assets/Courier.afm"),
+            Standard14::CourierBold => include_bytes!("../assets/Courier-Bold.afm"),
+            Standard14::CourierOblique => include_bytes!("../assets/Courier-Oblique.afm"),
+            Standard14::CourierBoldOblique => include_bytes!("../assets/Courier-BoldOblique.afm"),
+            Standard14::Helvetica => include_bytes!("../assets/Helvetica.afm"),
+            Standard14::HelveticaBold => include_bytes!("../assets/Helvetica-Bold.afm"),
+            Standard14::HelveticaOblique => include_bytes!("../assets/Helvetica-Oblique.afm"),
+            Standard14::HelveticaBoldOblique => {
+                include_bytes!("../assets/Helvetica-BoldOblique.afm")
+            }
+            Standard14::TimesRoman => include_bytes!("../assets/Times-Roman.afm"),
+            Standard14::TimesBold => include_bytes!("../assets/Times-Bold.afm"),
+            Standard14::TimesItalic => include_bytes!("../assets/Times-Italic.afm"),
+            Standard14::TimesBoldItalic => include_bytes!("../assets/Times-BoldItalic.afm"),
+            Standard14::Symbol => include_bytes!("../assets/Symbol.afm"),
+            Standard14::ZapfDingbats => include_bytes!("../assets/ZapfDingbats.afm"),
+        }
+    }
+
+    /// The metrics of the font, parsed the first time they are asked for.
+    /// Safe to call from several threads at once.
+    pub fn metrics(self) -> &'static FontMetrics {
+        METRICS[self as usize].get_or_init(|| {
+            FontMetrics::parse(self.afm_data()).expect("the bundled AFM files parse")
+        })
+    }
+
+    /// The metrics of the font as static tables, which unlike `metrics`
+    /// cost neither parsing nor allocation. They are generated from the
+    /// AFM files by `afm-codegen`.
+    pub fn static_metrics(self) -> &'static StaticFontMetrics {
+        match self {
+            Standard14::Courier => &tables::COURIER,
+            Standard14::CourierBold => &tables::COURIER_BOLD,
+            Standard14::CourierOblique => &tables::COURIER_OBLIQUE,
+            Standard14::CourierBoldOblique => &tables::COURIER_BOLD_OBLIQUE,
+            Standard14::Helvetica => &tables::HELVETICA,
+            Standard14::HelveticaBold => &tables::HELVETICA_BOLD,
+            Standard14::HelveticaOblique => &tables::HELVETICA_OBLIQUE,
+            Standard14::HelveticaBoldOblique => &tables::HELVETICA_BOLD_OBLIQUE,
+            Standard14::TimesRoman => &tables::TIMES_ROMAN,
+            Standard14::TimesBold => &tables::TIMES_BOLD,
+            Standard14::TimesItalic => &tables::TIMES_ITALIC,
+            Standard14::TimesBoldItalic => &tables::TIMES_BOLD_ITALIC,
+            Standard14::Symbol => &tables::SYMBOL,
+            Standard14::ZapfDingbats => &tables::ZAPF_DINGBATS,
+        }
+    }
+}
+
+impl fmt::Display for Standard14 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.postscript_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use {GlyphId, Metrics};
+
+    #[test]
+    fn standard_14_metrics() {
+        for (i, &font) in Standard14::ALL.iter().enumerate() {
+            assert_eq!(font as usize, i);
+            let metrics = font.metrics();
+            assert_eq!(metrics.font_name, font.postscript_name());
+            assert!(std::ptr::eq(metrics, font.metrics()));
+            let name = font.to_string();
+            assert_eq!(Standard14::from_postscript_name(&name), Some(font));
+        }
+        assert_eq!(Standard14::TimesRoman.metrics().unique_id(), Some(43068));
+        assert_eq!(Standard14::from_postscript_name("Arial"), None);
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| thread::spawn(|| Standard14::Symbol.metrics() as *const FontMetrics as usize))
+            .collect();
+        let addresses: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert!(addresses.iter().all(|&address| address == addresses[0]));
+    }
+
+    #[test]
+    fn standard_14_static_metrics() {
+        let mut tables = b"// Generated by afm-codegen, do not edit.\n".to_vec();
+        for &font in &Standard14::ALL {
+            let metrics = font.metrics();
+            tables.push(b'\n');
+            metrics.write_static_tables(&mut tables).unwrap();
+
+            let table = font.static_metrics();
+            assert_eq!(table.font_name, metrics.font_name);
+            assert_eq!(table.encoding_scheme, metrics.encoding_scheme);
+            assert_eq!(table.font_bbox, metrics.font_bbox);
+            assert_eq!(table.char_metrics.len(), metrics.char_metrics.len());
+            for (i, metric) in metrics.char_metrics.iter().enumerate() {
+                let id = GlyphId(i as u32);
+                assert_eq!(table.glyph_id(&metric.name), metrics.glyph_id(&metric.name));
+                assert_eq!(table.glyph_name(id), Some(&*metric.name));
+                assert_eq!(Metrics::width(table, id), metrics.width(id));
+                assert_eq!(Metrics::bbox(table, id), metrics.bbox(id));
+                for ligature in &metric.ligatures {
+                    let successor = ligature.successor;
+                    assert_eq!(table.ligature(id, successor), Some(ligature.ligature));
+                }
+                let code = metric.character_code;
+                assert_eq!(table.glyph_id_by_code(code), metrics.glyph_id_by_code(code));
+            }
+            for pair in &metrics.kern_pairs {
+                let (first, second) = (pair.first_kern_character, pair.second_kern_character);
+                assert_eq!(table.kerning(first, second), metrics.kerning(first, second));
+            }
+        }
+        let generated = include_str!("standard14_tables.rs");
+        assert!(
+            tables == generated.as_bytes(),
+            "src/standard14_tables.rs is out of date, regenerate it with afm-codegen"
+        );
+    }
+}
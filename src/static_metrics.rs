@@ -0,0 +1,254 @@
+use super::{FontMetrics, GlyphId, Ligature, Rect};
+
+/// The lookups `FontMetrics` and `StaticFontMetrics` share, for code that
+/// works with either.
+pub trait Metrics {
+    fn font_name(&self) -> &str;
+
+    fn font_bbox(&self) -> Rect;
+
+    /// The id of the glyph named `name`.
+    fn glyph_id(&self, name: &str) -> Option<GlyphId>;
+
+    /// The id of the glyph with the character code `code`.
+    fn glyph_id_by_code(&self, code: i32) -> Option<GlyphId>;
+
+    /// The name of the glyph `id`.
+    fn glyph_name(&self, id: GlyphId) -> Option<&str>;
+
+    /// The width `WX` of the glyph `id`.
+    fn width(&self, id: GlyphId) -> Option<f64>;
+
+    /// The bounding box `B` of the glyph `id`.
+    fn bbox(&self, id: GlyphId) -> Option<Rect>;
+
+    /// The glyph replacing `first` followed by `second`.
+    fn ligature(&self, first: GlyphId, second: GlyphId) -> Option<GlyphId>;
+
+    /// The `x` and `y` adjustment of the first pair of `kern_pairs` for
+    /// `first` followed by `second`.
+    fn kerning(&self, first: GlyphId, second: GlyphId) -> Option<(f64, f64)>;
+}
+
+/// The char metrics `StaticFontMetrics` keep of a glyph.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct StaticCharMetric {
+    pub name: &'static str,
+    pub character_code: i32,
+    pub wx: f64,
+    pub bbox: Rect,
+    pub ligatures: &'static [Ligature],
+}
+
+impl StaticCharMetric {
+    pub const fn new(
+        name: &'static str,
+        character_code: i32,
+        wx: f64,
+        bbox: Rect,
+        ligatures: &'static [Ligature],
+    ) -> StaticCharMetric {
+        StaticCharMetric {
+            name,
+            character_code,
+            wx,
+            bbox,
+            ligatures,
+        }
+    }
+}
+
+/// A kern pair of `StaticFontMetrics`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct StaticKernPair {
+    pub first_kern_character: GlyphId,
+    pub second_kern_character: GlyphId,
+    pub x: f64,
+    pub y: f64,
+}
+
+impl StaticKernPair {
+    pub const fn new(first: u32, second: u32, x: f64, y: f64) -> StaticKernPair {
+        StaticKernPair {
+            first_kern_character: GlyphId(first),
+            second_kern_character: GlyphId(second),
+            x,
+            y,
+        }
+    }
+}
+
+/// Font metrics held in static tables, which cost nothing to load. They
+/// are written as Rust code by `FontMetrics::write_static_tables`, and
+/// keep the widths, bounding boxes, ligatures and `kern_pairs` of the
+/// glyphs along with the most used header keys, but not `kern_pairs0`,
+/// `kern_pairs1`, `track_kern` or `composites`.
+///
+/// The lookups are those of `FontMetrics`, see also `Metrics`, and search
+/// the sorted `names`, `encoded` and `kern_pairs` in logarithmic time.
+#[derive(PartialEq, Debug)]
+pub struct StaticFontMetrics {
+    pub font_name: &'static str,
+    pub full_name: &'static str,
+    pub family_name: &'static str,
+    pub weight: &'static str,
+    pub encoding_scheme: &'static str,
+    pub character_set: &'static str,
+    pub font_bbox: Rect,
+    pub cap_height: f64,
+    pub x_height: f64,
+    pub ascender: f64,
+    pub descender: f64,
+    /// The values of direction 0.
+    pub underline_position: f64,
+    pub underline_thickness: f64,
+    pub italic_angle: f64,
+    pub is_fixed_pitch: bool,
+    pub char_metrics: &'static [StaticCharMetric],
+    /// The glyphs ordered by name, and by id among equal names.
+    pub names: &'static [GlyphId],
+    /// The glyphs with a character code ordered by code, and by id among
+    /// equal codes.
+    pub encoded: &'static [GlyphId],
+    /// Ordered by their first and then second glyph, and by their order in
+    /// the file among equal pairs.
+    pub kern_pairs: &'static [StaticKernPair],
+}
+
+impl StaticFontMetrics {
+    /// The id of the glyph named `name`.
+    pub fn glyph_id(&self, name: &str) -> Option<GlyphId> {
+        let name_of = |id: &GlyphId| self.char_metrics[id.index()].name;
+        let i = self.names.partition_point(|id| name_of(id) < name);
+        self.names.get(i).cloned().filter(|id| name_of(id) == name)
+    }
+
+    /// The char metrics of the glyph `id`.
+    pub fn glyph(&self, id: GlyphId) -> Option<&'static StaticCharMetric> {
+        self.char_metrics.get(id.index())
+    }
+
+    /// The name of the glyph `id`.
+    pub fn glyph_name(&self, id: GlyphId) -> Option<&'static str> {
+        self.glyph(id).map(|metric| metric.name)
+    }
+
+    /// The char metrics of the glyph named `name`.
+    pub fn glyph_by_name(&self, name: &str) -> Option<&'static StaticCharMetric> {
+        self.glyph_id(name).and_then(|id| self.glyph(id))
+    }
+
+    /// The id of the glyph with the character code `code`.
+    pub fn glyph_id_by_code(&self, code: i32) -> Option<GlyphId> {
+        let code_of = |id: &GlyphId| self.char_metrics[id.index()].character_code;
+        let i = self.encoded.partition_point(|id| code_of(id) < code);
+        self.encoded
+            .get(i)
+            .cloned()
+            .filter(|id| code_of(id) == code)
+    }
+
+    /// The char metrics of the glyph with the character code `code`.
+    pub fn glyph_by_code(&self, code: i32) -> Option<&'static StaticCharMetric> {
+        self.glyph_id_by_code(code).and_then(|id| self.glyph(id))
+    }
+
+    /// The glyphs with a character code, ordered by code.
+    pub fn encoded_glyphs(&self) -> impl Iterator<Item = &'static StaticCharMetric> {
+        let char_metrics = self.char_metrics;
+        self.encoded.iter().map(move |id| &char_metrics[id.index()])
+    }
+}
+
+impl Metrics for StaticFontMetrics {
+    fn font_name(&self) -> &str {
+        self.font_name
+    }
+
+    fn font_bbox(&self) -> Rect {
+        self.font_bbox
+    }
+
+    fn glyph_id(&self, name: &str) -> Option<GlyphId> {
+        StaticFontMetrics::glyph_id(self, name)
+    }
+
+    fn glyph_id_by_code(&self, code: i32) -> Option<GlyphId> {
+        StaticFontMetrics::glyph_id_by_code(self, code)
+    }
+
+    fn glyph_name(&self, id: GlyphId) -> Option<&str> {
+        StaticFontMetrics::glyph_name(self, id)
+    }
+
+    fn width(&self, id: GlyphId) -> Option<f64> {
+        self.glyph(id).map(|metric| metric.wx)
+    }
+
+    fn bbox(&self, id: GlyphId) -> Option<Rect> {
+        self.glyph(id).map(|metric| metric.bbox)
+    }
+
+    fn ligature(&self, first: GlyphId, second: GlyphId) -> Option<GlyphId> {
+        let ligatures = self.glyph(first)?.ligatures.iter();
+        ligatures
+            .filter(|ligature| ligature.successor == second)
+            .map(|ligature| ligature.ligature)
+            .next()
+    }
+
+    fn kerning(&self, first: GlyphId, second: GlyphId) -> Option<(f64, f64)> {
+        let key = |pair: &StaticKernPair| (pair.first_kern_character, pair.second_kern_character);
+        let i = self
+            .kern_pairs
+            .partition_point(|pair| key(pair) < (first, second));
+        let pair = self
+            .kern_pairs
+            .get(i)
+            .filter(|pair| key(pair) == (first, second))?;
+        Some((pair.x, pair.y))
+    }
+}
+
+impl Metrics for FontMetrics {
+    fn font_name(&self) -> &str {
+        &self.font_name
+    }
+
+    fn font_bbox(&self) -> Rect {
+        self.font_bbox
+    }
+
+    fn glyph_id(&self, name: &str) -> Option<GlyphId> {
+        FontMetrics::glyph_id(self, name)
+    }
+
+    fn glyph_id_by_code(&self, code: i32) -> Option<GlyphId> {
+        FontMetrics::glyph_id_by_code(self, code)
+    }
+
+    fn glyph_name(&self, id: GlyphId) -> Option<&str> {
+        FontMetrics::glyph_name(self, id)
+    }
+
+    fn width(&self, id: GlyphId) -> Option<f64> {
+        self.glyph(id).map(|metric| metric.wx)
+    }
+
+    fn bbox(&self, id: GlyphId) -> Option<Rect> {
+        self.glyph(id).map(|metric| metric.bbox)
+    }
+
+    fn ligature(&self, first: GlyphId, second: GlyphId) -> Option<GlyphId> {
+        let ligatures = self.glyph(first)?.ligatures.iter();
+        ligatures
+            .filter(|ligature| ligature.successor == second)
+            .map(|ligature| ligature.ligature)
+            .next()
+    }
+
+    fn kerning(&self, first: GlyphId, second: GlyphId) -> Option<(f64, f64)> {
+        let pair = self.kern_pairs.find(first, second)?;
+        Some((pair.x, pair.y))
+    }
+}
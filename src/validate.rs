@@ -0,0 +1,450 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::{CharMetric, FontMetrics, GlyphId, Rect, Span};
+
+/// How serious a `Diagnostic` is.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Severity {
+    /// The data is unusual but may be intended.
+    Warning,
+    /// The data contradicts itself or the format.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// The kinds of problems `FontMetrics::validate` reports. Their codes are
+/// stable and never reused.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Lint {
+    /// `Characters` disagrees with the number of char metrics.
+    CharacterCount,
+    /// A kern pair refers to a glyph without char metrics.
+    UnknownKernGlyph,
+    /// A ligature refers to a glyph without char metrics.
+    UnknownLigatureGlyph,
+    /// A composite part refers to a glyph without char metrics.
+    UnknownCompositeGlyph,
+    /// Several char metrics have the same name.
+    DuplicateGlyphName,
+    /// Several char metrics have the same character code.
+    DuplicateGlyphCode,
+    /// `IsFixedPitch` is true for a writing direction but the glyphs differ
+    /// in width in that direction.
+    VaryingFixedPitch,
+    /// A glyph extends beyond `FontBBox`.
+    GlyphOutsideFontBBox,
+    /// `FontName` or `FontBBox`, the header keys the format requires, is
+    /// missing, which for `FontBBox` includes a box of zeros.
+    /// `StartFontMetrics` and its version are required by the parser
+    /// already.
+    MissingKey,
+}
+
+impl Lint {
+    /// The stable code of the lint, such as `AFM001`.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            Lint::CharacterCount => "AFM001",
+            Lint::UnknownKernGlyph => "AFM002",
+            Lint::UnknownLigatureGlyph => "AFM003",
+            Lint::UnknownCompositeGlyph => "AFM004",
+            Lint::DuplicateGlyphName => "AFM005",
+            Lint::DuplicateGlyphCode => "AFM006",
+            Lint::VaryingFixedPitch => "AFM007",
+            Lint::GlyphOutsideFontBBox => "AFM008",
+            Lint::MissingKey => "AFM009",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match *self {
+            Lint::CharacterCount | Lint::VaryingFixedPitch | Lint::GlyphOutsideFontBBox => {
+                Severity::Warning
+            }
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// A problem found by `FontMetrics::validate`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Diagnostic {
+    pub lint: Lint,
+    pub message: String,
+    /// The line of the entry or header key at fault, if the metrics were
+    /// parsed and the problem lies with a single line.
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    fn new(lint: Lint, span: Option<Span>, message: String) -> Diagnostic {
+        Diagnostic {
+            lint,
+            message,
+            span,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.lint.code()
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.lint.severity()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity(), self.code(), self.message)
+    }
+}
+
+impl FontMetrics {
+    /// Checks the metrics for inconsistencies the parser accepts, such as
+    /// duplicate glyph names, or that editing `char_metrics` introduced,
+    /// such as kern pairs of glyphs that no longer exist.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut report =
+            |lint, span, message| diagnostics.push(Diagnostic::new(lint, span, message));
+
+        if self.font_name.is_empty() {
+            report(Lint::MissingKey, None, "missing FontName".to_string());
+        }
+        if self.font_bbox == Rect::default() {
+            report(
+                Lint::MissingKey,
+                self.header_span("FontBBox"),
+                "missing or empty FontBBox".to_string(),
+            );
+        }
+        if self.characters != 0 && self.characters as usize != self.char_metrics.len() {
+            report(
+                Lint::CharacterCount,
+                self.header_span("Characters"),
+                format!(
+                    "Characters is {} but there are {} char metrics",
+                    self.characters,
+                    self.char_metrics.len()
+                ),
+            );
+        }
+
+        let mut names = HashSet::new();
+        let mut codes = HashSet::new();
+        for metric in &self.char_metrics {
+            if !names.insert(&metric.name[..]) {
+                report(
+                    Lint::DuplicateGlyphName,
+                    metric.span,
+                    format!("duplicate glyph {}", metric.name),
+                );
+            }
+            if metric.character_code >= 0 && !codes.insert(metric.character_code) {
+                report(
+                    Lint::DuplicateGlyphCode,
+                    metric.span,
+                    format!(
+                        "duplicate code {} of glyph {}",
+                        metric.character_code, metric.name
+                    ),
+                );
+            }
+        }
+
+        for pair in self
+            .kern_pairs
+            .iter()
+            .chain(&self.kern_pairs0)
+            .chain(&self.kern_pairs1)
+        {
+            for &id in &[pair.first_kern_character, pair.second_kern_character] {
+                if self.glyph(id).is_none() {
+                    report(
+                        Lint::UnknownKernGlyph,
+                        pair.span,
+                        format!(
+                            "kern pair refers to glyph {} without char metrics",
+                            self.glyph_label(id)
+                        ),
+                    );
+                }
+            }
+        }
+        for metric in &self.char_metrics {
+            for ligature in &metric.ligatures {
+                for &id in &[ligature.successor, ligature.ligature] {
+                    if self.glyph(id).is_none() {
+                        report(
+                            Lint::UnknownLigatureGlyph,
+                            metric.span,
+                            format!(
+                                "ligature of {} refers to glyph {} without char metrics",
+                                metric.name,
+                                self.glyph_label(id)
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        for composite in &self.composites {
+            for part in &composite.parts {
+                if self.glyph(part.glyph).is_none() {
+                    report(
+                        Lint::UnknownCompositeGlyph,
+                        composite.span,
+                        format!(
+                            "composite {} refers to glyph {} without char metrics",
+                            composite.name,
+                            self.glyph_label(part.glyph)
+                        ),
+                    );
+                }
+            }
+        }
+
+        // The spans of IsFixedPitch lines do not say their direction, so
+        // direction 0 takes the first and direction 1 the last.
+        let mut fixed_pitch_spans = self
+            .header_spans
+            .iter()
+            .filter(|(key, _)| key == "IsFixedPitch");
+        let fixed_pitch_spans = [
+            fixed_pitch_spans.next().map(|&(_, span)| span),
+            fixed_pitch_spans.next_back().map(|&(_, span)| span),
+        ];
+        for (direction, metrics) in [&self.direction0, &self.direction1].iter().enumerate() {
+            if !metrics
+                .as_ref()
+                .is_some_and(|metrics| metrics.is_fixed_pitch)
+            {
+                continue;
+            }
+            let mut widths = self
+                .char_metrics
+                .iter()
+                .map(|metric| advance(metric, direction));
+            if let Some(first) = widths.next() {
+                if widths.any(|width| width != first) {
+                    let span = match direction {
+                        0 => fixed_pitch_spans[0],
+                        _ => fixed_pitch_spans[1].or(fixed_pitch_spans[0]),
+                    };
+                    report(
+                        Lint::VaryingFixedPitch,
+                        span,
+                        format!(
+                            "IsFixedPitch is true but the glyph widths of direction {} vary",
+                            direction
+                        ),
+                    );
+                }
+            }
+        }
+
+        if self.font_bbox != Rect::default() {
+            for metric in &self.char_metrics {
+                if !self.font_bbox.contains(&metric.bbox) {
+                    report(
+                        Lint::GlyphOutsideFontBBox,
+                        metric.span,
+                        format!("glyph {} extends beyond FontBBox", metric.name),
+                    );
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// The name of the glyph `id` for messages, or its number for an id
+    /// that refers to no glyph at all.
+    fn glyph_label(&self, id: GlyphId) -> String {
+        match self.glyph_name(id) {
+            Some(name) => name.to_string(),
+            None => id.0.to_string(),
+        }
+    }
+}
+
+/// The width of `metric` in the writing `direction`, from the vector `W0`
+/// or `W1`, else `W`, else the components, where `WX` and `WY` stand for
+/// `W0X` and `W0Y`.
+fn advance(metric: &CharMetric, direction: usize) -> (f64, f64) {
+    let either = |a: f64, b: f64| if a != 0.0 { a } else { b };
+    let (vector, components) = match direction {
+        0 => (
+            metric.w0,
+            (either(metric.w0x, metric.wx), either(metric.w0y, metric.wy)),
+        ),
+        _ => (metric.w1, (metric.w1x, metric.w1y)),
+    };
+    let given = |w: (f64, f64)| Some(w).filter(|&w| w != (0.0, 0.0));
+    given(vector).or(given(metric.w)).unwrap_or(components)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn validate_inconsistent_metrics() {
+        let input = b"StartFontMetrics 4.1
+FontName Test
+FontBBox 0 -200 1000 800
+Characters 4
+IsFixedPitch true
+StartCharMetrics 3
+C 65 ; WX 600 ; N A ; B 0 0 600 700 ; L D E ;
+C 65 ; WX 600 ; N B ; B 0 0 600 900 ;
+C 67 ; WX 500 ; N A ; B 0 0 500 700 ;
+EndCharMetrics
+StartKernData
+StartKernPairs 1
+KPX A C -80
+EndKernPairs
+EndKernData
+StartComposites 1
+CC Aacute 2 ; PCC A 0 0 ; PCC acute 100 200 ;
+EndComposites
+EndFontMetrics
+";
+        let metrics = FontMetrics::parse(input).unwrap();
+        let codes: Vec<_> = metrics
+            .validate()
+            .iter()
+            .map(|diagnostic| diagnostic.code())
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                "AFM001", "AFM006", "AFM005", "AFM002", "AFM003", "AFM003", "AFM004", "AFM007",
+                "AFM008",
+            ]
+        );
+        assert_eq!(
+            metrics.validate()[0].to_string(),
+            "warning[AFM001]: Characters is 4 but there are 3 char metrics"
+        );
+        assert_eq!(
+            metrics.validate()[3].to_string(),
+            "error[AFM002]: kern pair refers to glyph C without char metrics"
+        );
+        assert_eq!(
+            FontMetrics::default().validate()[0],
+            Diagnostic::new(Lint::MissingKey, None, "missing FontName".to_string())
+        );
+
+        // Without an IsFixedPitch line the widths may vary.
+        let input = b"StartFontMetrics 4.1
+FontName Test
+FontBBox 0 0 600 700
+StartCharMetrics 2
+C 65 ; WX 600 ; N A ;
+C 66 ; WX 500 ; N B ;
+EndCharMetrics
+EndFontMetrics
+";
+        let proportional = FontMetrics::parse(input).unwrap();
+        assert_eq!(proportional.validate(), vec![]);
+
+        // Metrics equal to parsed ones get the same diagnostics, only
+        // without spans.
+        let mut built = metrics.clone();
+        built.header_spans.clear();
+        for metric in built.char_metrics.edit().iter_mut() {
+            metric.span = None;
+        }
+        for pair in built.kern_pairs.edit().iter_mut() {
+            pair.span = None;
+        }
+        for composite in &mut built.composites {
+            composite.span = None;
+        }
+        assert_eq!(built, metrics);
+        let messages = |metrics: &FontMetrics| {
+            let diagnostics = metrics.validate().into_iter();
+            diagnostics
+                .map(|diagnostic| diagnostic.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(messages(&built), messages(&metrics));
+        built.font_bbox = Rect::default();
+        assert_eq!(
+            messages(&built)[0],
+            "error[AFM009]: missing or empty FontBBox"
+        );
+
+        // Direction 1 is checked on its own widths.
+        let input = b"StartFontMetrics 4.1
+FontName Test
+FontBBox 0 0 600 700
+MetricsSets 2
+IsFixedPitch false
+StartDirection 1
+IsFixedPitch true
+EndDirection
+StartCharMetrics 2
+C 65 ; WX 600 ; W1 0 -1000 ; N A ;
+C 66 ; WX 500 ; W1 0 -900 ; N B ;
+EndCharMetrics
+EndFontMetrics
+";
+        let vertical = FontMetrics::parse(input).unwrap();
+        let diagnostics = vertical.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].lint, Lint::VaryingFixedPitch);
+        assert_eq!(diagnostics[0].span.unwrap().line, 7);
+
+        // Diagnostics point at the lines of parsed entries.
+        let spans: Vec<_> = metrics
+            .validate()
+            .iter()
+            .map(|diagnostic| diagnostic.span.map(|span| span.line))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                Some(4),
+                Some(8),
+                Some(9),
+                Some(13),
+                Some(7),
+                Some(7),
+                Some(17),
+                Some(5),
+                Some(8),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_assets() {
+        for file in fs::read_dir(Path::new("assets")).unwrap() {
+            let path = file.unwrap().path();
+            if path.extension() != Some(OsStr::new("afm")) {
+                continue;
+            }
+            let metrics = FontMetrics::parse(&fs::read(&path).unwrap()).unwrap();
+            let errors: Vec<_> = metrics
+                .validate()
+                .into_iter()
+                .filter(|diagnostic| diagnostic.severity() == Severity::Error)
+                .collect();
+            assert_eq!(errors, vec![], "{}", path.display());
+        }
+    }
+}
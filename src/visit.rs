@@ -0,0 +1,290 @@
+use std::io::{self, Read};
+
+use super::{
+    decode_text, font_metrics, AfmError, CharMetricRef, CompositeRef, FontMetrics, GlyphId,
+    KernPairRef, Lines, ParseOptions, Sink, TrackKern, Warning,
+};
+use borrowed::HandWritten;
+use read::read_with;
+
+/// Which of `FontMetrics::kern_pairs`, `kern_pairs0` and `kern_pairs1` a
+/// kern pair belongs to, after the keyword starting its section.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum KernPairSet {
+    /// `StartKernPairs`
+    Pairs,
+    /// `StartKernPairs0`
+    Pairs0,
+    /// `StartKernPairs1`
+    Pairs1,
+}
+
+/// Receives the content of an AFM file while it is parsed, in the order of
+/// the file, see `FontMetrics::visit`. Every method does nothing by default.
+///
+/// Entries are checked like `FontMetrics::parse` does before they are
+/// passed on. Kern pairs, ligatures and composites may name glyphs without
+/// char metrics, which are reported as warnings at the end of the font.
+///
+/// ```
+/// # use afm::{AfmVisitor, CharMetricRef, FontMetrics};
+/// struct Widths(Vec<(String, f64)>);
+///
+/// impl<'a> AfmVisitor<'a> for Widths {
+///     fn char_metric(&mut self, metric: CharMetricRef<'a>) {
+///         self.0.push((metric.name.into_owned(), metric.wx));
+///     }
+/// }
+///
+/// let input = b"StartFontMetrics 4.1
+/// StartCharMetrics 1
+/// C 32 ; WX 250 ; N space ;
+/// EndCharMetrics
+/// EndFontMetrics
+/// ";
+/// let mut widths = Widths(Vec::new());
+/// FontMetrics::visit(input, &mut widths).unwrap();
+/// assert_eq!(widths.0, vec![("space".to_string(), 250.0)]);
+/// ```
+pub trait AfmVisitor<'a> {
+    /// A key outside of the char metrics, kern data and composites, such as
+    /// `FontName` or `Comment`, with the rest of its line. The keys of a
+    /// `StartDirection` block come between `StartDirection` and
+    /// `EndDirection`.
+    fn header_key(&mut self, _key: &str, _value: &str) {}
+
+    /// Like `header_key`, with the raw bytes of the value. By default
+    /// passes on the value decoded by `decode_text` to `header_key`.
+    fn header_value(&mut self, key: &str, value: &[u8]) {
+        self.header_key(key, &decode_text(value));
+    }
+
+    fn char_metric(&mut self, _metric: CharMetricRef<'a>) {}
+
+    fn kern_pair(&mut self, _set: KernPairSet, _pair: KernPairRef<'a>) {}
+
+    fn track_kern(&mut self, _track: TrackKern) {}
+
+    fn composite(&mut self, _composite: CompositeRef<'a>) {}
+}
+
+/// Hands what the parser reads on to a visitor.
+struct Visit<'v, V: 'v>(&'v mut V);
+
+impl<'a, 'v, V: AfmVisitor<'a>> Sink<'a> for Visit<'v, V> {
+    fn header_key(&mut self, key: &[u8], value: &[u8]) {
+        self.0.header_value(&String::from_utf8_lossy(key), value);
+    }
+
+    fn char_metric(&mut self, metric: CharMetricRef<'a>, _: &[GlyphId]) {
+        self.0.char_metric(metric);
+    }
+
+    fn kern_pair(&mut self, set: KernPairSet, pair: KernPairRef<'a>, _: &[GlyphId]) {
+        self.0.kern_pair(set, pair);
+    }
+
+    fn track_kern(&mut self, track: TrackKern) {
+        self.0.track_kern(track);
+    }
+
+    fn composite(&mut self, composite: CompositeRef<'a>, _: &[GlyphId]) {
+        self.0.composite(composite);
+    }
+}
+
+fn visit_lines<'a, V: AfmVisitor<'a>>(
+    lines: &mut Lines<'a>,
+    visitor: &mut V,
+) -> Result<(), AfmError> {
+    font_metrics(lines, &HandWritten, Visit(visitor)).map(|_| ())
+}
+
+impl FontMetrics {
+    /// Parses the content of an AFM file, passing it on to `visitor`
+    /// instead of building `FontMetrics`.
+    pub fn visit<'a, V: AfmVisitor<'a>>(input: &'a [u8], visitor: &mut V) -> Result<(), AfmError> {
+        FontMetrics::visit_with(input, &ParseOptions::default(), visitor).map(|_| ())
+    }
+
+    /// Like `visit`, returning the problems skipped in lenient mode. What
+    /// was passed to `visitor` before a failure stays with it.
+    pub fn visit_with<'a, V: AfmVisitor<'a>>(
+        input: &'a [u8],
+        options: &ParseOptions,
+        visitor: &mut V,
+    ) -> Result<Vec<Warning>, AfmError> {
+        let lines = &mut Lines::new(input, options);
+        visit_lines(lines, visitor)?;
+        Ok(lines.warnings.split_off(0))
+    }
+
+    /// Like `visit_with`, reading the input line by line from `reader`.
+    /// Parse errors are returned as `InvalidData` wrapping the `AfmError`.
+    pub fn visit_reader<R, V>(
+        reader: R,
+        options: &ParseOptions,
+        visitor: &mut V,
+    ) -> io::Result<Vec<Warning>>
+    where
+        R: Read,
+        V: for<'a> AfmVisitor<'a>,
+    {
+        read_with(reader, options, |lines| visit_lines(lines, visitor))
+            .map(|(_, warnings)| warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use KernPair;
+
+    /// Records the calls in a line each.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl<'a> AfmVisitor<'a> for Recorder {
+        fn header_key(&mut self, key: &str, value: &str) {
+            self.0.push(format!("{}={}", key, value));
+        }
+
+        fn char_metric(&mut self, metric: CharMetricRef<'a>) {
+            self.0.push(format!("C {} {}", metric.name, metric.wx));
+        }
+
+        fn kern_pair(&mut self, set: KernPairSet, pair: KernPairRef<'a>) {
+            let (first, second) = (pair.first_kern_character, pair.second_kern_character);
+            self.0
+                .push(format!("{:?} {} {} {}", set, first, second, pair.x));
+        }
+
+        fn track_kern(&mut self, track: TrackKern) {
+            self.0.push(format!("TrackKern {}", track.degree));
+        }
+
+        fn composite(&mut self, composite: CompositeRef<'a>) {
+            self.0
+                .push(format!("CC {} {}", composite.name, composite.parts.len()));
+        }
+    }
+
+    static AFM: &[u8] = b"StartFontMetrics 4.1
+Comment  Made up
+FontName Test
+MetricsSets 2
+StartDirection 1
+CharWidth 0 -1000
+EndDirection
+StartCharMetrics 3
+C 65 ; WX 667 ; N A ;
+C 86 ; WX 667 ; N V ;
+C 194 ; WX 333 ; N acute ;
+EndCharMetrics
+StartKernData
+StartTrackKern 1
+TrackKern -1 6 -0.1 72 -2
+EndTrackKern
+StartKernPairs1 1
+KPY A V 20
+EndKernPairs
+StartKernPairs 2
+KPX A V -80
+KPX A Aring -80
+EndKernPairs
+EndKernData
+StartComposites 1
+CC Aacute 2 ; PCC A 0 0 ; PCC acute 195 212 ;
+EndComposites
+EndFontMetrics
+";
+
+    #[test]
+    fn visit_in_file_order() {
+        let mut recorder = Recorder::default();
+        let options = ParseOptions {
+            strict: false,
+            ..ParseOptions::default()
+        };
+        let warnings = FontMetrics::visit_with(AFM, &options, &mut recorder).unwrap();
+        assert_eq!(
+            recorder.0,
+            vec![
+                "Comment=Made up",
+                "FontName=Test",
+                "MetricsSets=2",
+                "StartDirection=1",
+                "CharWidth=0 -1000",
+                "EndDirection=",
+                "C A 667",
+                "C V 667",
+                "C acute 333",
+                "TrackKern -1",
+                "Pairs1 A V 0",
+                "Pairs A V -80",
+                "Pairs A Aring -80",
+                "CC Aacute 2",
+            ]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].to_string(),
+            "22:1: unknown glyph `Aring` in KernPairs"
+        );
+
+        let mut strict = Recorder::default();
+        FontMetrics::visit(AFM, &mut strict).unwrap();
+        assert_eq!(strict.0, recorder.0);
+    }
+
+    #[test]
+    fn visit_raw_values() {
+        /// Keeps the raw `Notice`.
+        #[derive(Default)]
+        struct Notice(Vec<u8>);
+
+        impl<'a> AfmVisitor<'a> for Notice {
+            fn header_value(&mut self, key: &str, value: &[u8]) {
+                if key == "Notice" {
+                    self.0 = value.to_vec();
+                }
+            }
+        }
+
+        let input = b"StartFontMetrics 4.1\nNotice \xA9 Test \nEndFontMetrics\n";
+        let mut notice = Notice::default();
+        FontMetrics::visit(input, &mut notice).unwrap();
+        assert_eq!(notice.0, b"\xA9 Test");
+        let mut recorder = Recorder::default();
+        FontMetrics::visit(input, &mut recorder).unwrap();
+        assert_eq!(recorder.0, vec!["Notice=\u{A9} Test"]);
+    }
+
+    #[test]
+    fn visit_reader() {
+        /// Sums the kern pairs without keeping them.
+        #[derive(Default)]
+        struct Kerning(f64, usize);
+
+        impl<'a> AfmVisitor<'a> for Kerning {
+            fn kern_pair(&mut self, _: KernPairSet, pair: KernPairRef<'a>) {
+                self.0 += pair.x;
+                self.1 += 1;
+            }
+        }
+
+        let data = fs::read("assets/Times-Roman.afm").unwrap();
+        let metrics = FontMetrics::parse(&data).unwrap();
+        let mut kerning = Kerning::default();
+        let options = ParseOptions::default();
+        FontMetrics::visit_reader(&data[..], &options, &mut kerning).unwrap();
+        assert_eq!(kerning.1, metrics.kern_pairs.len());
+        let sum: f64 = metrics
+            .kern_pairs
+            .iter()
+            .map(|pair: &KernPair| pair.x)
+            .sum();
+        assert_eq!(kerning.0, sum);
+    }
+}
@@ -0,0 +1,566 @@
+use std::io::{self, Write};
+
+use super::{
+    is_keyword, name_char, CharMetric, Composite, DirectionMetrics, FontMetrics, GlyphId, KernPair,
+    Rect, TrackKern,
+};
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Whether `name` can be written as it is, outside of `KPH`.
+fn bare(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(name_char)
+}
+
+/// `name` if it can be written in a line of `;` separated keys.
+fn key_name(name: &str) -> io::Result<&str> {
+    if bare(name) && !name.contains(';') {
+        Ok(name)
+    } else {
+        Err(invalid_input(format!(
+            "glyph name {:?} cannot be written",
+            name
+        )))
+    }
+}
+
+fn write_bbox(out: &mut impl Write, bbox: &Rect) -> io::Result<()> {
+    write!(out, "{} {} {} {}", bbox.llx, bbox.lly, bbox.urx, bbox.ury)
+}
+
+fn write_string(out: &mut impl Write, key: &str, value: &str) -> io::Result<()> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "{} {}", key, value)
+}
+
+fn write_number(out: &mut impl Write, key: &str, value: f64) -> io::Result<()> {
+    if value == 0.0 {
+        return Ok(());
+    }
+    writeln!(out, "{} {}", key, value)
+}
+
+fn write_direction(out: &mut impl Write, metrics: &DirectionMetrics) -> io::Result<()> {
+    writeln!(out, "UnderlinePosition {}", metrics.underline_position)?;
+    writeln!(out, "UnderlineThickness {}", metrics.underline_thickness)?;
+    writeln!(out, "ItalicAngle {}", metrics.italic_angle)?;
+    if metrics.char_width != (0.0, 0.0) {
+        let (x, y) = metrics.char_width;
+        writeln!(out, "CharWidth {} {}", x, y)?;
+    }
+    writeln!(out, "IsFixedPitch {}", metrics.is_fixed_pitch)
+}
+
+/// The name of the glyph `id` refers to, with char metrics or in
+/// `unknown_glyphs`.
+fn glyph_name(metrics: &FontMetrics, id: GlyphId) -> io::Result<&str> {
+    metrics
+        .glyph_name(id)
+        .ok_or_else(|| invalid_input(format!("glyph {} has no char metrics", id.0)))
+}
+
+fn write_char_metric(
+    out: &mut impl Write,
+    metrics: &FontMetrics,
+    metric: &CharMetric,
+) -> io::Result<()> {
+    write!(out, "C {} ; WX {} ;", metric.character_code, metric.wx)?;
+    let widths = [
+        ("W0X", metric.w0x),
+        ("W1X", metric.w1x),
+        ("WY", metric.wy),
+        ("W0Y", metric.w0y),
+        ("W1Y", metric.w1y),
+    ];
+    for &(key, value) in &widths {
+        if value != 0.0 {
+            write!(out, " {} {} ;", key, value)?;
+        }
+    }
+    let vectors = [
+        ("W", metric.w),
+        ("W0", metric.w0),
+        ("W1", metric.w1),
+        ("VV", metric.vv),
+    ];
+    for &(key, (x, y)) in &vectors {
+        if (x, y) != (0.0, 0.0) {
+            write!(out, " {} {} {} ;", key, x, y)?;
+        }
+    }
+    if !metric.name.is_empty() {
+        write!(out, " N {} ;", metric.name)?;
+    }
+    write!(out, " B ")?;
+    write_bbox(out, &metric.bbox)?;
+    write!(out, " ;")?;
+    for ligature in &metric.ligatures {
+        let successor = key_name(glyph_name(metrics, ligature.successor)?)?;
+        let name = key_name(glyph_name(metrics, ligature.ligature)?)?;
+        write!(out, " L {} {} ;", successor, name)?;
+    }
+    for (key, value) in &metric.extra_keys {
+        write!(out, " {} {} ;", key, value)?;
+    }
+    writeln!(out)
+}
+
+fn write_track_kern(out: &mut impl Write, track: &TrackKern) -> io::Result<()> {
+    writeln!(
+        out,
+        "TrackKern {} {} {} {} {}",
+        track.degree, track.min_point_size, track.min_kern, track.max_point_size, track.max_kern
+    )
+}
+
+/// The name in the hex notation of `KPH`, one byte per character as the
+/// parser decodes it. Fails for characters beyond U+00FF.
+fn hex_name(name: &str) -> io::Result<String> {
+    let mut hex = String::from("<");
+    for c in name.chars() {
+        if c > '\u{FF}' {
+            return Err(invalid_input(format!(
+                "glyph name {:?} cannot be written",
+                name
+            )));
+        }
+        hex.push_str(&format!("{:02X}", c as u32));
+    }
+    hex.push('>');
+    Ok(hex)
+}
+
+/// Writes the shortest of `KPX`, `KPY` and `KP` that holds the pair, or
+/// `KPH` if a name cannot be written as is, such as one read from `KPH`.
+fn write_kern_pair(out: &mut impl Write, metrics: &FontMetrics, pair: &KernPair) -> io::Result<()> {
+    let names = (
+        glyph_name(metrics, pair.first_kern_character)?,
+        glyph_name(metrics, pair.second_kern_character)?,
+    );
+    if !bare(names.0) || !bare(names.1) {
+        let (first, second) = (hex_name(names.0)?, hex_name(names.1)?);
+        writeln!(out, "KPH {} {} {} {}", first, second, pair.x, pair.y)
+    } else if pair.y == 0.0 {
+        writeln!(out, "KPX {} {} {}", names.0, names.1, pair.x)
+    } else if pair.x == 0.0 {
+        writeln!(out, "KPY {} {} {}", names.0, names.1, pair.y)
+    } else {
+        writeln!(out, "KP {} {} {} {}", names.0, names.1, pair.x, pair.y)
+    }
+}
+
+fn write_kern_pairs(
+    out: &mut impl Write,
+    metrics: &FontMetrics,
+    start: &str,
+    pairs: &[KernPair],
+) -> io::Result<()> {
+    if pairs.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "{} {}", start, pairs.len())?;
+    for pair in pairs {
+        write_kern_pair(out, metrics, pair)?;
+    }
+    writeln!(out, "EndKernPairs")
+}
+
+fn write_composite(
+    out: &mut impl Write,
+    metrics: &FontMetrics,
+    composite: &Composite,
+) -> io::Result<()> {
+    write!(out, "CC {} {} ;", composite.name, composite.parts.len())?;
+    for part in &composite.parts {
+        write!(
+            out,
+            " PCC {} {} {} ;",
+            key_name(glyph_name(metrics, part.glyph)?)?,
+            part.x_displacement,
+            part.y_displacement
+        )?;
+    }
+    writeln!(out)
+}
+
+/// `key` if it reads back as an extra key, a name the format does not
+/// define.
+fn extra_key(key: &str) -> io::Result<&str> {
+    if bare(key) && !key.contains(';') && !is_keyword(key.as_bytes()) {
+        Ok(key)
+    } else {
+        Err(invalid_input(format!("key {:?} cannot be written", key)))
+    }
+}
+
+impl FontMetrics {
+    /// Fails if a number is not finite, a string value holds a line break
+    /// or a name or key would read back as something else.
+    fn check_writable(&self) -> io::Result<()> {
+        let strings = [
+            &self.font_name,
+            &self.full_name,
+            &self.family_name,
+            &self.weight,
+            &self.font_version,
+            &self.notice,
+            &self.encoding_scheme,
+            &self.character_set,
+        ];
+        let strings = strings.iter().cloned();
+        let strings = strings.chain(&self.comments);
+        let mut strings = strings.chain(self.extra_keys.iter().map(|(_, value)| value));
+        if let Some(value) = strings.find(|value| value.contains(['\r', '\n'])) {
+            return Err(invalid_input(format!(
+                "value {:?} cannot be written",
+                value
+            )));
+        }
+        for (key, _) in &self.extra_keys {
+            extra_key(key)?;
+        }
+        for metric in &self.char_metrics {
+            if !metric.name.is_empty() {
+                key_name(&metric.name)?;
+            }
+            for (key, value) in &metric.extra_keys {
+                extra_key(key)?;
+                if value.contains([';', '\r', '\n']) {
+                    let message = format!("value {:?} of key {} cannot be written", value, key);
+                    return Err(invalid_input(message));
+                }
+            }
+        }
+        for composite in &self.composites {
+            key_name(&composite.name)?;
+        }
+
+        let rect = |r: &Rect| vec![r.llx, r.lly, r.urx, r.ury];
+        let mut numbers = rect(&self.font_bbox);
+        numbers.extend_from_slice(&[
+            self.v_vector.0,
+            self.v_vector.1,
+            self.cap_height,
+            self.x_height,
+            self.ascender,
+            self.descender,
+            self.standard_horizontal_width,
+            self.standard_vertical_width,
+        ]);
+        for direction in self.direction0.iter().chain(&self.direction1) {
+            numbers.extend_from_slice(&[
+                direction.underline_position,
+                direction.underline_thickness,
+                direction.italic_angle,
+                direction.char_width.0,
+                direction.char_width.1,
+            ]);
+        }
+        for metric in &self.char_metrics {
+            numbers.extend(rect(&metric.bbox));
+            numbers.extend_from_slice(&[
+                metric.wx, metric.w0x, metric.w1x, metric.wy, metric.w0y, metric.w1y,
+            ]);
+            for &(x, y) in &[metric.w, metric.w0, metric.w1, metric.vv] {
+                numbers.extend_from_slice(&[x, y]);
+            }
+        }
+        for track in &self.track_kern {
+            numbers.extend_from_slice(&[
+                track.min_point_size,
+                track.min_kern,
+                track.max_point_size,
+                track.max_kern,
+            ]);
+        }
+        let pairs = self.kern_pairs.iter().chain(&self.kern_pairs0);
+        for pair in pairs.chain(&self.kern_pairs1) {
+            numbers.extend_from_slice(&[pair.x, pair.y]);
+        }
+        match numbers.iter().find(|number| !number.is_finite()) {
+            Some(number) => Err(invalid_input(format!(
+                "number {} cannot be written",
+                number
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the metrics as an AFM file of `afm_version`. Keys holding
+    /// their default value are left out where the format allows it. Fails
+    /// with `InvalidInput` if a kern pair, ligature or composite part
+    /// refers to a glyph neither in `char_metrics` nor in `unknown_glyphs`.
+    ///
+    /// Also fails with `InvalidInput`, before writing anything, for what
+    /// would not read back: numbers that are not finite and values with
+    /// line breaks. So do glyph names with spaces or `;`, and extra keys
+    /// the format defines, as lenient parsing keeps misplaced keys.
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        self.check_writable()?;
+        writeln!(out, "StartFontMetrics {}", self.afm_version)?;
+        for comment in &self.comments {
+            if comment.is_empty() {
+                writeln!(out, "Comment")?;
+            } else {
+                writeln!(out, "Comment {}", comment)?;
+            }
+        }
+        if self.metric_sets != 0 {
+            writeln!(out, "MetricsSets {}", self.metric_sets)?;
+        }
+        write_string(out, "FontName", &self.font_name)?;
+        write_string(out, "FullName", &self.full_name)?;
+        write_string(out, "FamilyName", &self.family_name)?;
+        write_string(out, "Weight", &self.weight)?;
+        write!(out, "FontBBox ")?;
+        write_bbox(out, &self.font_bbox)?;
+        writeln!(out)?;
+        write_string(out, "Version", &self.font_version)?;
+        write_string(out, "Notice", &self.notice)?;
+        write_string(out, "EncodingScheme", &self.encoding_scheme)?;
+        if self.mapping_scheme != 0 {
+            writeln!(out, "MappingScheme {}", self.mapping_scheme)?;
+        }
+        if self.esc_char != 0 {
+            writeln!(out, "EscChar {}", self.esc_char)?;
+        }
+        write_string(out, "CharacterSet", &self.character_set)?;
+        if self.characters != 0 {
+            writeln!(out, "Characters {}", self.characters)?;
+        }
+        if !self.is_base_font {
+            writeln!(out, "IsBaseFont false")?;
+        }
+        if self.v_vector != (0.0, 0.0) {
+            writeln!(out, "VVector {} {}", self.v_vector.0, self.v_vector.1)?;
+        }
+        if !self.is_fixed_v {
+            writeln!(out, "IsFixedV false")?;
+        }
+        write_number(out, "CapHeight", self.cap_height)?;
+        write_number(out, "XHeight", self.x_height)?;
+        write_number(out, "Ascender", self.ascender)?;
+        write_number(out, "Descender", self.descender)?;
+        write_number(out, "StdHW", self.standard_horizontal_width)?;
+        write_number(out, "StdVW", self.standard_vertical_width)?;
+        for (key, value) in &self.extra_keys {
+            if value.is_empty() {
+                writeln!(out, "{}", key)?;
+            } else {
+                writeln!(out, "{} {}", key, value)?;
+            }
+        }
+
+        // Without `MetricsSets` only direction 0 exists, and its metrics
+        // may be given at the top level.
+        if self.metric_sets == 0 {
+            if let Some(ref metrics) = self.direction0 {
+                write_direction(out, metrics)?;
+            }
+        } else {
+            let directions = [&self.direction0, &self.direction1];
+            for (direction, metrics) in directions.iter().enumerate() {
+                if let Some(ref metrics) = **metrics {
+                    writeln!(out, "StartDirection {}", direction)?;
+                    write_direction(out, metrics)?;
+                    writeln!(out, "EndDirection")?;
+                }
+            }
+        }
+
+        writeln!(out, "StartCharMetrics {}", self.char_metrics.len())?;
+        for metric in &self.char_metrics {
+            write_char_metric(out, self, metric)?;
+        }
+        writeln!(out, "EndCharMetrics")?;
+
+        let has_kerning = !self.track_kern.is_empty()
+            || !self.kern_pairs.is_empty()
+            || !self.kern_pairs0.is_empty()
+            || !self.kern_pairs1.is_empty();
+        if has_kerning {
+            writeln!(out, "StartKernData")?;
+            if !self.track_kern.is_empty() {
+                writeln!(out, "StartTrackKern {}", self.track_kern.len())?;
+                for track in &self.track_kern {
+                    write_track_kern(out, track)?;
+                }
+                writeln!(out, "EndTrackKern")?;
+            }
+            write_kern_pairs(out, self, "StartKernPairs", &self.kern_pairs)?;
+            write_kern_pairs(out, self, "StartKernPairs0", &self.kern_pairs0)?;
+            write_kern_pairs(out, self, "StartKernPairs1", &self.kern_pairs1)?;
+            writeln!(out, "EndKernData")?;
+        }
+
+        if !self.composites.is_empty() {
+            writeln!(out, "StartComposites {}", self.composites.len())?;
+            for composite in &self.composites {
+                write_composite(out, self, composite)?;
+            }
+            writeln!(out, "EndComposites")?;
+        }
+        writeln!(out, "EndFontMetrics")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::path::Path;
+    use {AfmVersion, CompositePart};
+
+    #[test]
+    fn write_kern_pairs_and_composites() {
+        let mut metrics = FontMetrics {
+            font_name: "Test".to_string(),
+            direction0: Some(DirectionMetrics::default()),
+            ..FontMetrics::default()
+        };
+        let glyph = |name: &str, code| CharMetric {
+            character_code: code,
+            name: name.to_string(),
+            ..CharMetric::default()
+        };
+        metrics.char_metrics = vec![glyph("A", 65), glyph("V", 86), glyph("acute", 194)].into();
+        let pair = |x, y| KernPair {
+            first_kern_character: GlyphId(0),
+            second_kern_character: GlyphId(1),
+            x,
+            y,
+            span: None,
+        };
+        metrics.kern_pairs = vec![pair(-80.0, 0.0), pair(0.0, 20.5), pair(-10.0, 5.0)].into();
+        metrics.composites = vec![Composite {
+            name: "Aacute".to_string(),
+            parts: vec![
+                CompositePart {
+                    glyph: GlyphId(0),
+                    x_displacement: 0,
+                    y_displacement: 0,
+                },
+                CompositePart {
+                    glyph: GlyphId(2),
+                    x_displacement: 195,
+                    y_displacement: 212,
+                },
+            ],
+            span: None,
+        }];
+
+        let mut out = Vec::new();
+        metrics.write_to(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text
+            .contains("StartKernPairs 3\nKPX A V -80\nKPY A V 20.5\nKP A V -10 5\nEndKernPairs\n"));
+        assert!(text.contains("CC Aacute 2 ; PCC A 0 0 ; PCC acute 195 212 ;\n"));
+        assert_eq!(FontMetrics::parse(text.as_bytes()).unwrap(), metrics);
+
+        // Names of glyphs without char metrics are kept.
+        metrics.unknown_glyphs = vec!["Aring".to_string()];
+        metrics.kern_pairs.edit().truncate(1);
+        metrics.kern_pairs.edit()[0].second_kern_character = GlyphId::unknown(0);
+        let mut out = Vec::new();
+        metrics.write_to(&mut out).unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("KPX A Aring -80\n"));
+        assert_eq!(FontMetrics::parse(&out).unwrap(), metrics);
+
+        // Names read from KPH that are no valid bare names go back to KPH.
+        metrics.unknown_glyphs = vec!["A ring".to_string()];
+        let mut out = Vec::new();
+        metrics.write_to(&mut out).unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("KPH <41> <412072696E67> -80 0\n"));
+        assert_eq!(FontMetrics::parse(&out).unwrap(), metrics);
+        metrics.unknown_glyphs = vec!["\u{410}".to_string()];
+        let err = metrics.write_to(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        metrics.kern_pairs.edit()[0].second_kern_character = GlyphId(3);
+        let err = metrics.write_to(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn write_version_and_extra_keys() {
+        let mut metrics = FontMetrics {
+            afm_version: AfmVersion::new(3, 0),
+            font_name: "Test".to_string(),
+            direction0: Some(DirectionMetrics::default()),
+            extra_keys: vec![
+                ("FooVendor".to_string(), "42".to_string()),
+                ("FooFlag".to_string(), String::new()),
+            ],
+            ..FontMetrics::default()
+        };
+        metrics.char_metrics.push(CharMetric {
+            name: "A".to_string(),
+            extra_keys: vec![("Q".to_string(), "1 2".to_string())],
+            ..CharMetric::default()
+        });
+        let mut out = Vec::new();
+        metrics.write_to(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("StartFontMetrics 3.0\n"));
+        assert!(text.contains("\nFooVendor 42\nFooFlag\n"));
+        assert!(text.contains(" N A ; B 0 0 0 0 ; Q 1 2 ;\n"));
+        assert_eq!(FontMetrics::parse(text.as_bytes()).unwrap(), metrics);
+    }
+
+    #[test]
+    fn refuse_what_cannot_be_read_back() {
+        let metrics = FontMetrics {
+            font_name: "Test".to_string(),
+            ..FontMetrics::default()
+        };
+        let refused = |change: &dyn Fn(&mut FontMetrics)| {
+            let mut metrics = metrics.clone();
+            change(&mut metrics);
+            let mut out = Vec::new();
+            let err = metrics.write_to(&mut out).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            out.is_empty()
+        };
+        assert!(refused(&|m| m.cap_height = f64::NAN));
+        assert!(refused(&|m| m.font_bbox.urx = f64::INFINITY));
+        assert!(refused(&|m| m.notice = "Two\nlines".to_string()));
+        assert!(refused(&|m| m.comments.push("Two\rlines".to_string())));
+        assert!(refused(&|m| m
+            .extra_keys
+            .push(("KPX".to_string(), "A V 0".to_string()))));
+        assert!(refused(&|m| m
+            .extra_keys
+            .push(("Two words".to_string(), String::new()))));
+        let glyph = |name: &str| CharMetric {
+            name: name.to_string(),
+            ..CharMetric::default()
+        };
+        assert!(refused(&|m| m.char_metrics.push(glyph("A B"))));
+        assert!(refused(&|m| m.char_metrics.push(glyph("A;B"))));
+        assert!(refused(&|m| {
+            m.char_metrics.push(glyph("A"));
+            m.char_metrics.edit()[0].wx = f64::NEG_INFINITY;
+        }));
+    }
+
+    #[test]
+    fn round_trip_assets() {
+        for file in fs::read_dir(Path::new("assets")).unwrap() {
+            let path = file.unwrap().path();
+            if path.extension() != Some(OsStr::new("afm")) {
+                continue;
+            }
+            let metrics = FontMetrics::parse(&fs::read(&path).unwrap()).unwrap();
+            let mut out = Vec::new();
+            metrics.write_to(&mut out).unwrap();
+            let written = FontMetrics::parse(&out)
+                .unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+            assert_eq!(written, metrics, "{}", path.display());
+        }
+    }
+}